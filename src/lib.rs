@@ -1,4 +1,9 @@
 pub mod manager;
+pub mod metrics;
 pub mod models;
+pub mod proxy;
+pub mod rate_limiter;
+pub mod redaction;
+mod telemetry;
 pub mod utils;
 pub mod webservices;
@@ -1,17 +1,81 @@
 use crate::manager::callback::{ManagerApiCallback, ManagerApiCallbackHandler};
-use crate::manager::cs_messages_external::{ProtoBalanceHistoryListReq, ProtoCsPayloadType, ProtoManagerClosePositionReq, ProtoOrderDetailsReq, ProtoTraderListReq};
+use crate::manager::clock::ServerClock;
+use crate::manager::cs_messages_external::{
+    ProtoAssetClassListReq, ProtoAssetListReq, ProtoBalanceHistoryListReq, ProtoBookRequestType,
+    ProtoCountryListReq, ProtoCreatePriceStreamReq, ProtoCrudDynamicLeverageReq,
+    ProtoCrudGslScheduleReq, ProtoCrudLiquidityFeedSymbolReq, ProtoCrudManagerReq,
+    ProtoCrudMaxAutoExecutionSizeProfileReq, ProtoCrudOperation,
+    ProtoCrudTradeNotificationProfileReq, ProtoCsPayloadType, ProtoDealingSettingsReq,
+    ProtoDealingSettingsRes, ProtoDeletePriceStreamReq, ProtoDepositWithdraw, ProtoDynamicLeverage,
+    ProtoDynamicLeverageListReq, ProtoExposureSymbolListReq, ProtoGetSymbolsForConversionReq,
+    ProtoGslSchedule, ProtoGslScheduleListReq, ProtoLightSymbol, ProtoLiquidityFeed,
+    ProtoLiquidityFeedListReq, ProtoLiquidityFeedStatusReq, ProtoLiquidityFeedStatusRes,
+    ProtoLiquidityFeedSymbol, ProtoLiquidityFeedSymbolListReq, ProtoManager, ProtoManagerAuthReq,
+    ProtoManagerByIdReq, ProtoManagerClosePositionReq, ProtoManagerGetAuthTokenReq,
+    ProtoManagerListReq, ProtoMaxAutoExecutionSizeProfile, ProtoMaxAutoExecutionSizeProfileListReq,
+    ProtoOrderDetailsReq, ProtoPriceStream, ProtoPriceStreamListReq,
+    ProtoRecalculateAccountMarginReq, ProtoRecalculateDynamicLeverageMarginReq,
+    ProtoRecalculateSymbolMarginReq, ProtoServerSetting, ProtoServerSettingsReq, ProtoServerTimeReq,
+    ProtoSymbolCategoryListReq, ProtoTradeNotificationProfile, ProtoTradeNotificationProfileListReq,
+    ProtoTraderListReq, ProtoUpdateDealingSettingsReq, ProtoUpdatePriceStreamReq,
+    ProtoUpdateServerSettingsReq, ProtoVersionReq,
+};
+#[cfg(feature = "dangerous-ops")]
+use crate::manager::cs_messages_external::{
+    ProtoExecutionEvent, ProtoExecutionType, ProtoForceClosePositionReq, ProtoForceOpenPositionReq,
+};
+use crate::manager::endpoints::{EndpointRotation, ManagerApiEndpoint};
+use crate::manager::event_bus::{OverflowPolicy, Subscription, SubscriptionFilter};
+use crate::manager::exposure::{ExposureChange, ExposureReport};
+use crate::manager::liquidity::{get_status_change, LiquidityFeedStatusChange};
+use crate::manager::models::{ManagerApiMessage, ManagerApiResponse};
+use crate::manager::options::{ManagerApiClientOptions, ReconnectBackoff};
+use crate::manager::recording::TrafficRecorder;
+use crate::manager::probe::probe_endpoint;
+use crate::manager::relay::{LocalRelay, RelayTarget, RelayTls};
+use crate::manager::reference_data::ReferenceData;
+#[cfg(feature = "dangerous-ops")]
+use crate::manager::models::ManagerApiEvent;
 use crate::manager::serialization::ManagerApiSerializerFactory;
+use crate::manager::sessions::TraderSessionRegistry;
+use crate::manager::shutdown::ManagerApiShutdownReport;
+use crate::manager::transport::{
+    parse_host_port, parse_server_name, ManagerApiTlsConfig, ManagerApiTransport,
+};
+use crate::metrics::ConnectorMetrics;
 use crate::models::ManagerCreds;
+use crate::proxy::ProxyConfig;
+use crate::rate_limiter::RateLimiter;
+use crate::redaction::redact;
+use crate::utils::generate_password_hash;
+use crate::webservices::retry::BalanceChangeVerifier;
+use chrono::Utc;
 use my_tcp_sockets::{TcpClient, TcpClientSocketSettings, TlsSettings};
 use rust_extensions::Logger;
-use std::sync::Arc;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex, OnceCell, RwLock};
+
+const SERVER_CLOCK_SYNC_INTERVAL: Duration = Duration::from_secs(60);
 
 pub struct ManagerApiClient<T: ManagerApiCallbackHandler + Send + Sync + 'static> {
-    tcp_client: tokio::sync::Mutex<Option<TcpClient>>,
+    tcp_client: Arc<Mutex<Option<TcpClient>>>,
     logger: Arc<dyn Logger + Send + Sync + 'static>,
     inner_client: ManagerApiCallback<T>,
     config_wrapper: Arc<ManagerApiConfigWrapper>,
+    server_clock: Arc<RwLock<Option<ServerClock>>>,
+    is_server_clock_sync_started: AtomicBool,
+    recorder: Option<Arc<TrafficRecorder>>,
+    options: ManagerApiClientOptions,
+    is_stopped: Arc<AtomicBool>,
+    is_reconnect_supervisor_started: AtomicBool,
+    is_failover_started: AtomicBool,
+    is_reference_data_sync_started: AtomicBool,
+    /// Count of authorizations when the whole trader list was last loaded by `get_trader_id`.
+    trader_list_loaded_at: Mutex<Option<u64>>,
 }
 
 impl<T: ManagerApiCallbackHandler + Send + Sync + 'static> ManagerApiClient<T> {
@@ -21,14 +85,31 @@ impl<T: ManagerApiCallbackHandler + Send + Sync + 'static> ManagerApiClient<T> {
         creds: Arc<dyn ManagerCreds + Send + Sync>,
         logger: Arc<dyn Logger + Send + Sync + 'static>,
     ) -> Self {
-        let config_wrapper = Arc::new(ManagerApiConfigWrapper {
-            config: Arc::clone(&config),
+        Self::new_with_options(
+            handler,
+            config,
             creds,
-        });
+            logger,
+            ManagerApiClientOptions::default(),
+        )
+    }
+
+    pub fn new_with_options(
+        handler: Arc<T>,
+        config: Arc<dyn ManagerApiConfig + Send + Sync>,
+        creds: Arc<dyn ManagerCreds + Send + Sync>,
+        logger: Arc<dyn Logger + Send + Sync + 'static>,
+        options: ManagerApiClientOptions,
+    ) -> Self {
+        let config_wrapper = Arc::new(ManagerApiConfigWrapper::new(
+            Arc::clone(&config),
+            creds,
+            logger.clone(),
+        ));
         let callback = ManagerApiCallback::new(
             handler,
             Arc::clone(&config_wrapper),
-            Duration::from_secs(30),
+            options.clone(),
             logger.clone(),
         );
 
@@ -37,40 +118,548 @@ impl<T: ManagerApiCallbackHandler + Send + Sync + 'static> ManagerApiClient<T> {
             tcp_client: Default::default(),
             logger,
             config_wrapper,
+            server_clock: Default::default(),
+            is_server_clock_sync_started: AtomicBool::new(false),
+            recorder: None,
+            options,
+            is_stopped: Arc::new(AtomicBool::new(false)),
+            is_reconnect_supervisor_started: AtomicBool::new(false),
+            is_failover_started: AtomicBool::new(false),
+            is_reference_data_sync_started: AtomicBool::new(false),
+            trader_list_loaded_at: Mutex::new(None),
         }
     }
 
+    /// Limits the requests with the `MANAGER_API_GROUP` bucket of the limiter. The limiter can be
+    /// shared with other clients.
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.inner_client = self.inner_client.with_rate_limiter(rate_limiter);
+        self
+    }
+
+    /// Records all inbound and outbound messages of the next connections.
+    pub fn with_recorder(mut self, recorder: Arc<TrafficRecorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Connects to the active endpoint, switching to the next ones if it fails. If all
+    /// endpoints fail, the client keeps reconnecting every `reconnect_timeout`, but not with
+    /// the reconnect backoff: its attempts start after a lost connection, so `connect` must
+    /// be called again.
     pub async fn connect(&self) -> Result<(), String> {
         self.logger.write_info(
             "ManagerApiClient.connect".into(),
             "Starting tcp connection..".into(),
             None,
         );
-        let domain_name = self.config_wrapper.get_domain().await;
-        let tcp_client = TcpClient::new(domain_name, self.config_wrapper.clone())
-            .set_disconnect_timeout(Duration::from_secs(40))
-            .set_reconnect_timeout(Duration::from_secs(20))
-            .set_seconds_to_ping(10);
-
-        tcp_client
-            .start(
-                Arc::new(ManagerApiSerializerFactory::default()),
-                self.inner_client.clone(),
-                Arc::clone(&self.logger),
+        self.config_wrapper.validate().await?;
+        self.is_stopped.store(false, Ordering::SeqCst);
+        self.inner_client.resume();
+        self.config_wrapper.reset_to_primary_endpoint();
+        let endpoints_count = self.config_wrapper.get_endpoints().await.len();
+
+        for attempt in 1..=endpoints_count {
+            let endpoint_index = self.config_wrapper.get_active_endpoint_index().await;
+            // a new client for every endpoint: with the reconnect backoff the client does not
+            // reconnect by itself
+            let tcp_client = start_tcp_client(
+                &self.inner_client,
+                &self.config_wrapper,
+                &self.options,
+                self.recorder.as_ref(),
+                &self.logger,
             )
             .await;
-        self.tcp_client.lock().await.replace(tcp_client);
 
-        self.inner_client.wait_until_connected().await?;
+            if let Some(previous_client) = self.tcp_client.lock().await.replace(tcp_client) {
+                previous_client.stop().await;
+            }
+
+            let Err(err) = self.inner_client.wait_until_connected().await else {
+                break;
+            };
+
+            if attempt == endpoints_count {
+                return Err(err);
+            }
+
+            // the endpoint may be already switched by a failed authorization
+            if endpoint_index == self.config_wrapper.get_active_endpoint_index().await {
+                self.config_wrapper.rotate_endpoint().await;
+            }
+
+            self.logger.write_warning(
+                "ManagerApiClient.connect".into(),
+                redact(&format!(
+                    "Failed to connect: {}. Switched to endpoint {}",
+                    err,
+                    self.config_wrapper.get_active_endpoint().await.url
+                )),
+                None,
+            );
+        }
+
+        if let Err(err) = self.sync_server_clock().await {
+            self.logger.write_warning(
+                "ManagerApiClient.connect".into(),
+                redact(&format!("Failed to sync server clock: {}", err)),
+                None,
+            );
+        }
+
+        if let Err(err) = self.load_reference_data().await {
+            self.logger.write_warning(
+                "ManagerApiClient.connect".into(),
+                redact(&format!("Failed to load reference data: {}", err)),
+                None,
+            );
+        }
+
+        if !self.is_server_clock_sync_started.swap(true, Ordering::SeqCst) {
+            self.start_server_clock_sync();
+        }
+
+        if !self
+            .is_reference_data_sync_started
+            .swap(true, Ordering::SeqCst)
+        {
+            self.start_reference_data_sync();
+        }
+
+        if !self.is_failover_started.swap(true, Ordering::SeqCst) {
+            self.start_failover();
+        }
+
+        if let Some(backoff) = &self.options.reconnect_backoff {
+            if !self
+                .is_reconnect_supervisor_started
+                .swap(true, Ordering::SeqCst)
+            {
+                self.start_reconnect_supervisor(backoff.clone());
+            }
+        }
 
         Ok(())
     }
 
+    /// Counters of the connection and received and sent messages.
+    pub fn get_metrics(&self) -> Arc<ConnectorMetrics> {
+        self.inner_client.get_metrics()
+    }
+
+    /// Cached countries, assets, asset classes and symbol categories. The cache is loaded on
+    /// connect and every reconnect and kept current from the changed and deleted events.
+    pub fn get_reference_data(&self) -> Arc<RwLock<ReferenceData>> {
+        self.inner_client.get_reference_data()
+    }
+
+    /// Subscribes to the received messages matching the filter. Every subscription has its own
+    /// buffer of `capacity` messages and `policy` defines what happens when it is full, so
+    /// subscribers do not block each other unless `OverflowPolicy::Block` is used.
+    /// The handler still receives all messages.
+    pub async fn subscribe(
+        &self,
+        filter: SubscriptionFilter,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> Subscription {
+        self.inner_client
+            .get_event_bus()
+            .subscribe(filter, capacity, policy)
+            .await
+    }
+
+    /// Trader sessions tracked from the logon and logout events received since the last
+    /// connect or reconnect. The online sessions are cleared when the connection is lost.
+    pub fn get_trader_sessions(&self) -> Arc<RwLock<TraderSessionRegistry>> {
+        self.inner_client.get_trader_sessions()
+    }
+
+    /// Reloads all reference entities into the cache. The cache is also reloaded after every
+    /// reconnect, so the changes missed while disconnected are not lost.
+    pub async fn load_reference_data(&self) -> Result<(), String> {
+        load_reference_data(&self.inner_client).await
+    }
+
+    /// Returns the chain of symbols to convert the first asset to the last one.
+    /// Chains are cached until any asset is changed.
+    pub async fn get_symbols_for_conversion(
+        &self,
+        first_asset_id: i64,
+        last_asset_id: i64,
+    ) -> Result<Vec<ProtoLightSymbol>, String> {
+        let reference_data = self.get_reference_data();
+
+        if let Some(symbols) = reference_data
+            .read()
+            .await
+            .get_symbols_for_conversion(first_asset_id, last_asset_id)
+        {
+            return Ok(symbols.clone());
+        }
+
+        let req = ProtoGetSymbolsForConversionReq {
+            payload_type: None,
+            first_asset_id,
+            last_asset_id,
+        };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoSymbolsForConversionReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::SymbolsForConversionRes(res)) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        reference_data.write().await.set_symbols_for_conversion(
+            first_asset_id,
+            last_asset_id,
+            res.symbol.clone(),
+        );
+
+        Ok(res.symbol)
+    }
+
+    /// Returns the manager's token that can be used for authorization in cID, e.g. with
+    /// `WebservicesApiClient::set_cid_token` for the cTID routes.
+    pub async fn get_cid_auth_token(&self) -> Result<String, String> {
+        let req = ProtoManagerGetAuthTokenReq { payload_type: None };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoManagerGetAuthTokenReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::ManagerGetAuthTokenRes(res)) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.token)
+    }
+
+    /// Returns current UNIX-timestamp of the cServer in milliseconds.
+    pub async fn server_time(&self) -> Result<i64, String> {
+        get_server_time(&self.inner_client).await
+    }
+
+    /// Returns the number of current cServer version.
+    pub async fn server_version(&self) -> Result<String, String> {
+        let req = ProtoVersionReq { payload_type: None };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoVersionReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::VersionRes(res)) = message else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.version)
+    }
+
+    /// Measures the offset of the cServer clock. The clock is synced on connect and then
+    /// refreshed every `SERVER_CLOCK_SYNC_INTERVAL` while the client is alive.
+    pub async fn sync_server_clock(&self) -> Result<ServerClock, String> {
+        let clock = measure_server_clock(&self.inner_client).await?;
+        self.server_clock.write().await.replace(clock);
+
+        Ok(clock)
+    }
+
+    /// Returns the last measured offset of the cServer clock.
+    pub async fn get_server_clock(&self) -> Option<ServerClock> {
+        *self.server_clock.read().await
+    }
+
+    /// Returns the current cServer time. Use it instead of the host clock for trading day
+    /// boundaries. Returns `None` until the clock is synced.
+    pub async fn server_now(&self) -> Option<chrono::DateTime<Utc>> {
+        self.get_server_clock().await.map(|clock| clock.server_now())
+    }
+
+    fn start_server_clock_sync(&self) {
+        let inner_client = self.inner_client.clone();
+        let logger = Arc::clone(&self.logger);
+        let server_clock: Weak<RwLock<Option<ServerClock>>> = Arc::downgrade(&self.server_clock);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(SERVER_CLOCK_SYNC_INTERVAL).await;
+
+                let Some(server_clock) = server_clock.upgrade() else {
+                    // client is dropped
+                    break;
+                };
+
+                if !inner_client.is_connected().await {
+                    continue;
+                }
+
+                match measure_server_clock(&inner_client).await {
+                    Ok(clock) => {
+                        server_clock.write().await.replace(clock);
+                    }
+                    Err(err) => {
+                        logger.write_warning(
+                            "ManagerApiClient.sync_server_clock".into(),
+                            redact(&format!("Failed to sync server clock: {}", err)),
+                            None,
+                        );
+                    }
+                }
+            }
+        });
+    }
+
+    /// Reloads the reference data after every reconnect: the changed and deleted events sent
+    /// while the client was disconnected are lost.
+    fn start_reference_data_sync(&self) {
+        let inner_client = self.inner_client.clone();
+        let logger = Arc::clone(&self.logger);
+        let tcp_client: Weak<Mutex<Option<TcpClient>>> = Arc::downgrade(&self.tcp_client);
+        let mut authorizations = inner_client.subscribe_authorizations();
+        // the current connection is loaded by connect
+        authorizations.borrow_and_update();
+
+        tokio::spawn(async move {
+            while authorizations.changed().await.is_ok() {
+                authorizations.borrow_and_update();
+
+                if tcp_client.upgrade().is_none() {
+                    // client is dropped
+                    break;
+                }
+
+                if let Err(err) = load_reference_data(&inner_client).await {
+                    logger.write_warning(
+                        "ManagerApiClient.sync_reference_data".into(),
+                        redact(&format!("Failed to reload reference data: {}", err)),
+                        None,
+                    );
+                }
+            }
+        });
+    }
+
+    /// Replaces the fixed reconnect timeout of the tcp client, which is disabled by
+    /// `get_tcp_reconnect_timeout`: once the connection is lost the client is stopped and
+    /// a new one is started after every backoff delay, so each attempt is counted.
+    fn start_reconnect_supervisor(&self, backoff: ReconnectBackoff) {
+        let inner_client = self.inner_client.clone();
+        let config_wrapper = Arc::clone(&self.config_wrapper);
+        let options = self.options.clone();
+        let recorder = self.recorder.clone();
+        let logger = Arc::clone(&self.logger);
+        let is_stopped = Arc::clone(&self.is_stopped);
+        let tcp_client: Weak<Mutex<Option<TcpClient>>> = Arc::downgrade(&self.tcp_client);
+        let mut disconnects = inner_client.subscribe_disconnects();
+
+        tokio::spawn(async move {
+            let mut is_disconnected = false;
+
+            loop {
+                if !is_disconnected && disconnects.changed().await.is_err() {
+                    break;
+                }
+
+                // disconnects of the failed attempts below are handled by this reconnect
+                disconnects.borrow_and_update();
+
+                let Some(tcp_client) = tcp_client.upgrade() else {
+                    // client is dropped
+                    break;
+                };
+
+                if is_stopped.load(Ordering::SeqCst) {
+                    is_disconnected = false;
+                    continue;
+                }
+
+                if let Some(current_client) = tcp_client.lock().await.take() {
+                    current_client.stop().await;
+                }
+
+                let mut attempts = 0;
+
+                let is_reconnected = loop {
+                    if backoff.is_exhausted(attempts) {
+                        logger.write_warning(
+                            "ManagerApiClient.reconnect".into(),
+                            format!("Gave up reconnecting after {} attempts", attempts),
+                            None,
+                        );
+                        inner_client.on_reconnect_gave_up(attempts).await;
+                        break false;
+                    }
+
+                    tokio::time::sleep(backoff.get_delay(attempts)).await;
+                    attempts += 1;
+
+                    if is_stopped.load(Ordering::SeqCst) {
+                        break false;
+                    }
+
+                    let endpoint_index = config_wrapper.get_active_endpoint_index().await;
+                    let new_client = start_tcp_client(
+                        &inner_client,
+                        &config_wrapper,
+                        &options,
+                        recorder.as_ref(),
+                        &logger,
+                    )
+                    .await;
+
+                    match inner_client.wait_until_connected().await {
+                        Ok(()) => {
+                            tcp_client.lock().await.replace(new_client);
+                            disconnects.borrow_and_update();
+                            break true;
+                        }
+                        Err(err) => {
+                            new_client.stop().await;
+
+                            if endpoint_index == config_wrapper.get_active_endpoint_index().await
+                            {
+                                config_wrapper.rotate_endpoint().await;
+                            }
+
+                            logger.write_warning(
+                                "ManagerApiClient.reconnect".into(),
+                                redact(&format!("Reconnect attempt {} failed: {}", attempts, err)),
+                                None,
+                            );
+                        }
+                    }
+                };
+
+                // the connection can be lost again before its disconnect was marked as seen
+                is_disconnected = is_reconnected && !inner_client.is_connected().await;
+            }
+        });
+    }
+
+    /// Switches back to the primary endpoint once it is reachable again, probed not more often
+    /// than `primary_probe_interval`. Without the reconnect backoff it also switches to the next
+    /// endpoint while the connection cannot be restored.
+    fn start_failover(&self) {
+        let inner_client = self.inner_client.clone();
+        let config_wrapper = Arc::clone(&self.config_wrapper);
+        let options = self.options.clone();
+        let logger = Arc::clone(&self.logger);
+        let is_stopped = Arc::clone(&self.is_stopped);
+        let tcp_client: Weak<Mutex<Option<TcpClient>>> = Arc::downgrade(&self.tcp_client);
+
+        tokio::spawn(async move {
+            let mut last_probe: Option<Instant> = None;
+
+            loop {
+                tokio::time::sleep(options.failover_check_interval).await;
+
+                if tcp_client.upgrade().is_none() {
+                    // client is dropped
+                    break;
+                }
+
+                if is_stopped.load(Ordering::SeqCst) {
+                    continue;
+                }
+
+                let endpoints = config_wrapper.get_endpoints().await;
+
+                if endpoints.len() < 2 {
+                    continue;
+                }
+
+                if !inner_client.is_connected().await {
+                    if options.reconnect_backoff.is_none() {
+                        let endpoint = config_wrapper.rotate_endpoint().await;
+                        logger.write_warning(
+                            "ManagerApiClient.failover".into(),
+                            format!("Not connected. Switched to endpoint {}", endpoint.url),
+                            None,
+                        );
+                    }
+
+                    continue;
+                }
+
+                if config_wrapper.get_active_endpoint_index().await == 0 {
+                    continue;
+                }
+
+                if last_probe
+                    .is_some_and(|probed_at| probed_at.elapsed() < options.primary_probe_interval)
+                {
+                    continue;
+                }
+
+                last_probe = Some(Instant::now());
+                let primary_url = &endpoints[0].url;
+                let probe = tokio::time::timeout(
+                    options.wait_timeout,
+                    config_wrapper.probe_endpoint(&endpoints[0]),
+                )
+                .await;
+
+                if let Ok(Ok(())) = probe {
+                    logger.write_info(
+                        "ManagerApiClient.failover".into(),
+                        format!("Primary endpoint {} recovered. Switching back", primary_url),
+                        None,
+                    );
+                    config_wrapper.reset_to_primary_endpoint();
+                    inner_client.drop_connection().await;
+                }
+            }
+        });
+    }
+
+    /// Endpoint which is used for the current connection or the next connection attempt.
+    pub async fn get_active_endpoint(&self) -> ManagerApiEndpoint {
+        self.config_wrapper.get_active_endpoint().await
+    }
+
     pub async fn is_connected(&self) -> bool {
         self.inner_client.is_connected().await
     }
 
+    /// Stops accepting new requests, sends the queued ones, waits until the sent ones are
+    /// confirmed or the deadline passes and disconnects. The report contains the requests with unknown outcome, e.g.
+    /// a `req_close_position` that was sent but not executed yet.
+    pub async fn shutdown(&self, deadline: Duration) -> ManagerApiShutdownReport {
+        let report = self.inner_client.shutdown(deadline).await;
+
+        if let Some(recorder) = &self.recorder {
+            if let Err(err) = recorder.flush() {
+                self.logger.write_warning(
+                    "ManagerApiClient.shutdown".into(),
+                    redact(&format!("Failed to flush recorder: {}", err)),
+                    None,
+                );
+            }
+        }
+
+        self.disconnect().await;
+
+        if !report.is_clean() {
+            self.logger.write_warning(
+                "ManagerApiClient.shutdown".into(),
+                format!(
+                    "Shutdown with {} unconfirmed requests",
+                    report.unconfirmed_requests.len()
+                ),
+                None,
+            );
+        }
+
+        report
+    }
+
     pub async fn disconnect(&self) {
+        self.is_stopped.store(true, Ordering::SeqCst);
         let tcp_client = self.tcp_client.lock().await.take();
 
         if let Some(tcp_client) = tcp_client {
@@ -99,6 +688,123 @@ impl<T: ManagerApiCallbackHandler + Send + Sync + 'static> ManagerApiClient<T> {
             .await
     }
 
+    /// Searches the balance history of the trader for the deposit or withdrawal with
+    /// the `external_id` made after `from_timestamp` (UNIX timestamp in milliseconds).
+    /// The client implements `BalanceChangeVerifier` for the Webservices client with it.
+    pub async fn find_balance_change(
+        &self,
+        trader_id: i64,
+        external_id: &str,
+        from_timestamp: i64,
+    ) -> Result<Option<ProtoDepositWithdraw>, String> {
+        let to_timestamp = Utc::now().timestamp_millis() + 60_000;
+
+        find_in_history_pages(
+            from_timestamp,
+            to_timestamp,
+            |from_timestamp, to_timestamp| async move {
+                let req = ProtoBalanceHistoryListReq {
+                    payload_type: None,
+                    trader_id: Some(trader_id),
+                    from_timestamp,
+                    to_timestamp,
+                };
+                let message = self
+                    .inner_client
+                    .request(req, ProtoCsPayloadType::ProtoBalanceHistoryListReq)
+                    .await?;
+
+                let ManagerApiMessage::Response(ManagerApiResponse::BalanceHistoryRes(res)) =
+                    message
+                else {
+                    return Err(get_unexpected_response_error(&message));
+                };
+
+                Ok(HistoryPage {
+                    entities: res.deposit_withdraw,
+                    has_more: res.has_more == Some(true),
+                })
+            },
+            |change| Some(change.change_balance_timestamp),
+            |change| change.external_id.as_deref() == Some(external_id),
+        )
+        .await
+    }
+
+    /// Returns the trader id of the login from the login index of the session registry. If
+    /// the login is not indexed, loads the trader list page by page and indexes the traders.
+    /// A login that is not found by a full load is not loaded again until the next
+    /// reconnect: the index is kept current by `TraderChangedEvent` meanwhile.
+    pub async fn get_trader_id(&self, login: i64) -> Result<i64, String> {
+        let trader_sessions = self.get_trader_sessions();
+
+        if let Some(trader_id) = trader_sessions.read().await.get_trader_id(login) {
+            return Ok(trader_id as i64);
+        }
+
+        let authorizations = *self.inner_client.subscribe_authorizations().borrow();
+        let mut trader_list_loaded_at = self.trader_list_loaded_at.lock().await;
+
+        // the index may be filled while waiting for the lock
+        if let Some(trader_id) = trader_sessions.read().await.get_trader_id(login) {
+            return Ok(trader_id as i64);
+        }
+
+        let not_found_error = || format!("Trader with login {} is not found", login);
+
+        if *trader_list_loaded_at == Some(authorizations) {
+            return Err(not_found_error());
+        }
+
+        let to_timestamp = Utc::now().timestamp_millis() + 60_000;
+        let trader = find_in_history_pages(
+            0,
+            to_timestamp,
+            |from_timestamp, to_timestamp| {
+                let trader_sessions = Arc::clone(&trader_sessions);
+
+                async move {
+                    let req = ProtoTraderListReq {
+                        payload_type: None,
+                        from_timestamp,
+                        to_timestamp,
+                        group_id: None,
+                        hide_ib_parameters: Some(true),
+                        only_sub_accounts: None,
+                    };
+                    let message = self
+                        .inner_client
+                        .request(req, ProtoCsPayloadType::ProtoTraderListReq)
+                        .await?;
+
+                    let ManagerApiMessage::Response(ManagerApiResponse::TraderListRes(res)) =
+                        message
+                    else {
+                        return Err(get_unexpected_response_error(&message));
+                    };
+
+                    trader_sessions.write().await.set_traders(&res.trader);
+
+                    Ok(HistoryPage {
+                        entities: res.trader,
+                        has_more: res.has_more,
+                    })
+                }
+            },
+            |trader| trader.registration_timestamp,
+            |trader| trader.login == login,
+        )
+        .await?;
+
+        match trader {
+            Some(trader) => Ok(trader.trader_id),
+            None => {
+                trader_list_loaded_at.replace(authorizations);
+                Err(not_found_error())
+            }
+        }
+    }
+
     pub async fn req_balance_history(&self, req: ProtoBalanceHistoryListReq) -> Result<(), String> {
         self.inner_client
             .send(req, ProtoCsPayloadType::ProtoBalanceHistoryListReq)
@@ -110,11 +816,1027 @@ impl<T: ManagerApiCallbackHandler + Send + Sync + 'static> ManagerApiClient<T> {
         self.inner_client
             .send(req, ProtoCsPayloadType::ProtoOrderDetailsReq)
             .await
-    }}
+    }
+
+    pub async fn get_dynamic_leverages(&self) -> Result<Vec<ProtoDynamicLeverage>, String> {
+        let req = ProtoDynamicLeverageListReq { payload_type: None };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoDynamicLeverageListReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::DynamicLeverageListRes(res)) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.leverage)
+    }
+
+    /// Creates, updates or deletes a Dynamic Leverage entity. Returns ID of the entity.
+    pub async fn crud_dynamic_leverage(
+        &self,
+        operation: ProtoCrudOperation,
+        leverage: ProtoDynamicLeverage,
+    ) -> Result<i64, String> {
+        let req = ProtoCrudDynamicLeverageReq {
+            payload_type: None,
+            operation: operation as i32,
+            leverage,
+        };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoCrudDynamicLeverageReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::CrudDynamicLeverageRes(res)) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.leverage_id)
+    }
+
+    /// Recalculates margin of the open positions that use the specified Dynamic Leverage.
+    pub async fn recalculate_dynamic_leverage_margin(&self, leverage_id: i64) -> Result<(), String> {
+        let req = ProtoRecalculateDynamicLeverageMarginReq {
+            payload_type: None,
+            leverage_id,
+        };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoRecalculateDynamicLeverageReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::RecalculateDynamicLeverageMarginRes(
+            _,
+        )) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(())
+    }
+
+    /// Recalculates margin of the trader's open positions with the current leverage of
+    /// the trader. Resolves when cServer confirms the recalculation; changed margins are
+    /// delivered as `MarginChangedEvent`.
+    pub async fn recalculate_account_margin(&self, login: i64) -> Result<(), String> {
+        let req = ProtoRecalculateAccountMarginReq {
+            payload_type: None,
+            trader_id: self.get_trader_id(login).await?,
+            leverage_in_cents: None,
+        };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoRecalculateAccountMarginReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::RecalculateAccountMarginRes(_)) =
+            message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(())
+    }
+
+    /// Recalculates margin of the symbol positions in the group after symbol margin settings
+    /// were changed. Resolves when cServer confirms the recalculation; changed margins are
+    /// delivered as `MarginChangedEvent`.
+    pub async fn recalculate_symbol_margin(
+        &self,
+        group_id: i64,
+        symbol_id: i64,
+        leverage_id: i64,
+    ) -> Result<(), String> {
+        let req = ProtoRecalculateSymbolMarginReq {
+            payload_type: None,
+            group_id,
+            symbol_id,
+            leverage_id,
+        };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoRecalculateSymbolMarginReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::RecalculateSymbolMarginRes(_)) =
+            message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(())
+    }
+
+    pub async fn get_gsl_schedules(&self) -> Result<Vec<ProtoGslSchedule>, String> {
+        let req = ProtoGslScheduleListReq { payload_type: None };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoGslScheduleListReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::GslScheduleListRes(res)) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.schedule)
+    }
+
+    /// Creates, updates or deletes a Guaranteed Stop Loss schedule. Returns ID of the schedule.
+    pub async fn crud_gsl_schedule(
+        &self,
+        operation: ProtoCrudOperation,
+        schedule: ProtoGslSchedule,
+    ) -> Result<i64, String> {
+        let req = ProtoCrudGslScheduleReq {
+            payload_type: None,
+            operation: operation as i32,
+            schedule,
+        };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoCrudGslScheduleReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::CrudGslScheduleRes(res)) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.schedule_id)
+    }
+
+    /// Gets net exposure per symbol for the specified books.
+    pub async fn get_exposure(
+        &self,
+        book_type: ProtoBookRequestType,
+    ) -> Result<ExposureReport, String> {
+        get_exposure(&self.inner_client, book_type).await
+    }
+
+    /// Polls exposure with the specified interval and sends the records that changed since
+    /// the previous poll. The first message contains all records. Polling stops when the
+    /// receiver is dropped.
+    pub fn watch_exposure(
+        &self,
+        book_type: ProtoBookRequestType,
+        interval: Duration,
+    ) -> mpsc::Receiver<Vec<ExposureChange>> {
+        let (sender, receiver) = mpsc::channel(16);
+        let inner_client = self.inner_client.clone();
+        let logger = self.logger.clone();
+
+        tokio::spawn(async move {
+            let mut previous = ExposureReport::default();
+
+            while !sender.is_closed() {
+                match get_exposure(&inner_client, book_type).await {
+                    Ok(report) => {
+                        let changes = report.get_changes(&previous);
+                        previous = report;
+
+                        if !changes.is_empty() && sender.send(changes).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(err) => {
+                        logger.write_warning(
+                            "ManagerApiClient.watch_exposure".into(),
+                            redact(&format!("Failed to get exposure: {}", err)),
+                            None,
+                        );
+                    }
+                }
+
+                tokio::time::sleep(interval).await;
+            }
+        });
+
+        receiver
+    }
+
+    pub async fn get_liquidity_feeds(&self) -> Result<Vec<ProtoLiquidityFeed>, String> {
+        get_liquidity_feeds(&self.inner_client).await
+    }
+
+    /// Gets the state of the pricing and trading connections of the Liquidity Feed.
+    pub async fn get_liquidity_feed_status(
+        &self,
+        liquidity_feed_id: i64,
+    ) -> Result<ProtoLiquidityFeedStatusRes, String> {
+        get_liquidity_feed_status(&self.inner_client, liquidity_feed_id).await
+    }
+
+    /// Polls the status of all enabled Liquidity Feeds with the specified interval and sends
+    /// a change when the connection state of a feed differs from the previous poll.
+    /// The first poll sends the state of every feed. Polling stops when the receiver is dropped.
+    pub fn watch_liquidity_feeds(
+        &self,
+        interval: Duration,
+    ) -> mpsc::Receiver<LiquidityFeedStatusChange> {
+        let (sender, receiver) = mpsc::channel(16);
+        let inner_client = self.inner_client.clone();
+        let logger = self.logger.clone();
+
+        tokio::spawn(async move {
+            let mut statuses: HashMap<i64, ProtoLiquidityFeedStatusRes> = HashMap::new();
+
+            while !sender.is_closed() {
+                let feeds = match get_liquidity_feeds(&inner_client).await {
+                    Ok(feeds) => feeds,
+                    Err(err) => {
+                        logger.write_warning(
+                            "ManagerApiClient.watch_liquidity_feeds".into(),
+                            redact(&format!("Failed to get liquidity feeds: {}", err)),
+                            None,
+                        );
+                        Vec::new()
+                    }
+                };
+
+                for feed in feeds.iter().filter(|feed| feed.enabled != Some(false)) {
+                    let id = feed.liquidity_feed_id;
+                    let status = match get_liquidity_feed_status(&inner_client, id).await {
+                        Ok(status) => status,
+                        Err(err) => {
+                            logger.write_warning(
+                                "ManagerApiClient.watch_liquidity_feeds".into(),
+                                redact(&format!(
+                                    "Failed to get liquidity feed {} status: {}",
+                                    id, err
+                                )),
+                                None,
+                            );
+                            continue;
+                        }
+                    };
+                    let change = get_status_change(feed, statuses.get(&id), &status);
+                    statuses.insert(id, status);
+
+                    if let Some(change) = change {
+                        if sender.send(change).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+
+                tokio::time::sleep(interval).await;
+            }
+        });
+
+        receiver
+    }
+
+    pub async fn get_liquidity_feed_symbols(
+        &self,
+    ) -> Result<Vec<ProtoLiquidityFeedSymbol>, String> {
+        let req = ProtoLiquidityFeedSymbolListReq { payload_type: None };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoLiquidityFeedSymbolListReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::LiquidityFeedSymbolListRes(res)) =
+            message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.liquidity_feed_symbol)
+    }
+
+    /// Creates, updates or deletes a link of a Liquidity Feed to a Symbol.
+    /// Returns ID of the Liquidity Feed Symbol.
+    pub async fn crud_liquidity_feed_symbol(
+        &self,
+        operation: ProtoCrudOperation,
+        liquidity_feed_symbol: ProtoLiquidityFeedSymbol,
+    ) -> Result<i64, String> {
+        let req = ProtoCrudLiquidityFeedSymbolReq {
+            payload_type: None,
+            operation: operation as i32,
+            liquidity_feed_symbol,
+        };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoCrudLiquidityFeedSymbolReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::CrudLiquidityFeedSymbolRes(res)) =
+            message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.liquidity_feed_symbol_id)
+    }
+
+    pub async fn get_price_streams(&self) -> Result<Vec<ProtoPriceStream>, String> {
+        let req = ProtoPriceStreamListReq { payload_type: None };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoPriceStreamListReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::PriceStreamListRes(res)) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.price_stream)
+    }
+
+    /// Creates a Price Stream with its symbol markups. Returns ID of the created Price Stream.
+    pub async fn create_price_stream(&self, price_stream: ProtoPriceStream) -> Result<i64, String> {
+        let req = ProtoCreatePriceStreamReq {
+            payload_type: None,
+            price_stream,
+        };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoPriceStreamCreateReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::CreatePriceStreamRes(res)) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.price_stream_id)
+    }
+
+    /// Updates a Price Stream. Symbol markups are created, updated or deleted according to
+    /// the operation of each `update_symbol` item.
+    pub async fn update_price_stream(&self, req: ProtoUpdatePriceStreamReq) -> Result<(), String> {
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoPriceStreamUpdateReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::UpdatePriceStreamRes(_)) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(())
+    }
+
+    pub async fn delete_price_stream(&self, price_stream_id: i64) -> Result<(), String> {
+        let req = ProtoDeletePriceStreamReq {
+            payload_type: None,
+            price_stream_id,
+        };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoPriceStreamDeleteReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::DeletePriceStreamRes(_)) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(())
+    }
+
+    pub async fn get_server_settings(&self) -> Result<Vec<ProtoServerSetting>, String> {
+        let req = ProtoServerSettingsReq { payload_type: None };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoServerSettingsReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::ServerSettingsRes(res)) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.server_setting)
+    }
+
+    /// Updates only the specified server settings. Other managers receive
+    /// `ManagerApiEvent::ServerSettingsChangedEvent`.
+    pub async fn update_server_settings(
+        &self,
+        server_settings: Vec<ProtoServerSetting>,
+    ) -> Result<(), String> {
+        let req = ProtoUpdateServerSettingsReq {
+            payload_type: None,
+            server_setting: server_settings,
+        };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoUpdateServerSettingsReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::UpdateServerSettingsRes(_)) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(())
+    }
+
+    pub async fn get_dealing_settings(&self) -> Result<ProtoDealingSettingsRes, String> {
+        let req = ProtoDealingSettingsReq { payload_type: None };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoDealingSettingsReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::DealingSettingsRes(res)) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res)
+    }
+
+    /// Updates only the fields specified in the request. Other managers receive
+    /// `ManagerApiEvent::DealingSettingsUpdatedEvent`.
+    pub async fn update_dealing_settings(
+        &self,
+        req: ProtoUpdateDealingSettingsReq,
+    ) -> Result<(), String> {
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoUpdateDealingSettingsReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::UpdateDealingSettingsRes(_)) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(())
+    }
+
+    pub async fn get_managers(&self) -> Result<Vec<ProtoManager>, String> {
+        let req = ProtoManagerListReq { payload_type: None };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoManagerListReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::ManagerListRes(res)) = message else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.manager)
+    }
+
+    pub async fn get_manager(&self, manager_id: i64) -> Result<ProtoManager, String> {
+        let req = ProtoManagerByIdReq {
+            payload_type: None,
+            manager_id,
+        };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoManagerByIdReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::ManagerByIdRes(res)) = message else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.manager)
+    }
+
+    /// Creates a Manager. `manager_id` must be 0 and `password_hash` should be generated with
+    /// `generate_password_hash`. Returns ID of the created Manager.
+    pub async fn create_manager(&self, manager: ProtoManager) -> Result<i64, String> {
+        self.crud_manager(ProtoCrudOperation::ProtoCreate, manager).await
+    }
+
+    /// Replaces the Manager entity including groups and permissions.
+    /// Unspecified optional fields are reset to their default values.
+    pub async fn update_manager(&self, manager: ProtoManager) -> Result<(), String> {
+        self.crud_manager(ProtoCrudOperation::ProtoUpdate, manager).await?;
+
+        Ok(())
+    }
+
+    /// Disables login of the Manager while keeping all its other settings.
+    pub async fn disable_manager(&self, manager_id: i64) -> Result<(), String> {
+        let mut manager = self.get_manager(manager_id).await?;
+        manager.enabled = false;
+        self.crud_manager(ProtoCrudOperation::ProtoUpdateDiff, manager)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn crud_manager(
+        &self,
+        operation: ProtoCrudOperation,
+        manager: ProtoManager,
+    ) -> Result<i64, String> {
+        let req = ProtoCrudManagerReq {
+            payload_type: None,
+            operation: operation as i32,
+            manager,
+        };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoCrudManagerReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::CrudManagerRes(res)) = message else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.manager_id)
+    }
+
+    pub async fn get_trade_notification_profiles(
+        &self,
+    ) -> Result<Vec<ProtoTradeNotificationProfile>, String> {
+        let req = ProtoTradeNotificationProfileListReq { payload_type: None };
+        let message = self
+            .inner_client
+            .request(
+                req,
+                ProtoCsPayloadType::ProtoCrudTradeNotificationProfileListReq,
+            )
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::TradeNotificationProfileListRes(res)) =
+            message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.trade_notification_profile)
+    }
+
+    /// Creates, updates or deletes a Trade Notification Profile which defines the volume
+    /// thresholds for `notifyBigVolume` of execution events. Returns ID of the Profile.
+    pub async fn crud_trade_notification_profile(
+        &self,
+        operation: ProtoCrudOperation,
+        trade_notification_profile: ProtoTradeNotificationProfile,
+    ) -> Result<i64, String> {
+        let req = ProtoCrudTradeNotificationProfileReq {
+            payload_type: None,
+            operation: operation as i32,
+            trade_notification_profile,
+        };
+        let message = self
+            .inner_client
+            .request(req, ProtoCsPayloadType::ProtoCrudTradeNotificationProfileReq)
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::CrudTradeNotificationProfileRes(res)) =
+            message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.trade_notification_profile_id)
+    }
+
+    pub async fn get_max_auto_execution_size_profiles(
+        &self,
+    ) -> Result<Vec<ProtoMaxAutoExecutionSizeProfile>, String> {
+        let req = ProtoMaxAutoExecutionSizeProfileListReq { payload_type: None };
+        let message = self
+            .inner_client
+            .request(
+                req,
+                ProtoCsPayloadType::ProtoCrudMaxAutoExecutionSizeProfileListReq,
+            )
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::MaxAutoExecutionSizeProfileListRes(
+            res,
+        )) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.profile)
+    }
+
+    /// Creates, updates (`ProtoUpdateDiff`) or deletes a Max Auto Execution Size Profile.
+    /// Orders larger than the symbol threshold are passed to the Dealer. Returns ID of the Profile.
+    pub async fn crud_max_auto_execution_size_profile(
+        &self,
+        operation: ProtoCrudOperation,
+        profile: ProtoMaxAutoExecutionSizeProfile,
+    ) -> Result<i64, String> {
+        let req = ProtoCrudMaxAutoExecutionSizeProfileReq {
+            payload_type: None,
+            operation: operation as i32,
+            profile,
+        };
+        let message = self
+            .inner_client
+            .request(
+                req,
+                ProtoCsPayloadType::ProtoCrudMaxAutoExecutionSizeProfileReq,
+            )
+            .await?;
+
+        let ManagerApiMessage::Response(ManagerApiResponse::CrudMaxAutoExecutionSizeProfileRes(
+            res,
+        )) = message
+        else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        Ok(res.profile_id)
+    }
+}
+
+async fn start_tcp_client<T: ManagerApiCallbackHandler + Send + Sync + 'static>(
+    inner_client: &ManagerApiCallback<T>,
+    config_wrapper: &Arc<ManagerApiConfigWrapper>,
+    options: &ManagerApiClientOptions,
+    recorder: Option<&Arc<TrafficRecorder>>,
+    logger: &Arc<dyn Logger + Send + Sync + 'static>,
+) -> TcpClient {
+    let domain_name = config_wrapper.get_domain().await;
+    let tcp_client = TcpClient::new(domain_name, config_wrapper.clone())
+        .set_disconnect_timeout(options.disconnect_timeout)
+        .set_reconnect_timeout(options.get_tcp_reconnect_timeout())
+        .set_seconds_to_ping(options.get_seconds_to_ping());
+
+    let mut serializer_factory =
+        ManagerApiSerializerFactory::default().with_metrics(inner_client.get_metrics());
+
+    if let Some(recorder) = recorder {
+        serializer_factory = serializer_factory.with_recorder(Arc::clone(recorder));
+    }
+
+    tcp_client
+        .start(
+            Arc::new(serializer_factory),
+            inner_client.clone(),
+            Arc::clone(logger),
+        )
+        .await;
+
+    tcp_client
+}
+
+fn get_unexpected_response_error(message: &ManagerApiMessage) -> String {
+    redact(&format!("Unexpected response: {:?}", message))
+}
+
+/// Entities of a history request and whether the requested window has more of them.
+struct HistoryPage<E> {
+    entities: Vec<E>,
+    has_more: bool,
+}
+
+/// Requests the pages of a history between the timestamps (UNIX timestamps in milliseconds)
+/// until an entity matches. Entities without a timestamp are matched but do not move
+/// the window.
+async fn find_in_history_pages<E, Fut>(
+    mut from_timestamp: i64,
+    mut to_timestamp: i64,
+    mut request_page: impl FnMut(i64, i64) -> Fut,
+    get_timestamp: impl Fn(&E) -> Option<i64>,
+    is_match: impl Fn(&E) -> bool,
+) -> Result<Option<E>, String>
+where
+    Fut: Future<Output = Result<HistoryPage<E>, String>>,
+{
+    loop {
+        let page = request_page(from_timestamp, to_timestamp).await?;
+        let timestamps: Vec<i64> = page.entities.iter().filter_map(&get_timestamp).collect();
+
+        if let Some(entity) = page.entities.into_iter().find(|entity| is_match(entity)) {
+            return Ok(Some(entity));
+        }
+
+        if !page.has_more {
+            return Ok(None);
+        }
+
+        match get_next_page_window(&timestamps, from_timestamp, to_timestamp) {
+            Some(window) => (from_timestamp, to_timestamp) = window,
+            None => return Ok(None),
+        }
+    }
+}
+
+/// Returns the window of the page after the one with the timestamps. cServer does not
+/// document the order of a page, so it is taken from the page: a descending page continues
+/// before its oldest entity, an ascending one after its newest. Entities with the same
+/// timestamp as the edge of the page that did not fit into it are skipped. Returns `None`
+/// if the window can not be narrowed.
+fn get_next_page_window(
+    timestamps: &[i64],
+    from_timestamp: i64,
+    to_timestamp: i64,
+) -> Option<(i64, i64)> {
+    let (first, last) = (*timestamps.first()?, *timestamps.last()?);
+    let (from, to) = if first >= last {
+        (from_timestamp, timestamps.iter().min()? - 1)
+    } else {
+        (timestamps.iter().max()? + 1, to_timestamp)
+    };
+
+    (from <= to && (from > from_timestamp || to < to_timestamp)).then_some((from, to))
+}
+
+async fn get_liquidity_feeds<T: ManagerApiCallbackHandler + Send + Sync + 'static>(
+    inner_client: &ManagerApiCallback<T>,
+) -> Result<Vec<ProtoLiquidityFeed>, String> {
+    let req = ProtoLiquidityFeedListReq { payload_type: None };
+    let message = inner_client
+        .request(req, ProtoCsPayloadType::ProtoLiquidityFeedListReq)
+        .await?;
+
+    let ManagerApiMessage::Response(ManagerApiResponse::LiquidityFeedListRes(res)) = message else {
+        return Err(get_unexpected_response_error(&message));
+    };
+
+    Ok(res.feed)
+}
+
+async fn get_liquidity_feed_status<T: ManagerApiCallbackHandler + Send + Sync + 'static>(
+    inner_client: &ManagerApiCallback<T>,
+    liquidity_feed_id: i64,
+) -> Result<ProtoLiquidityFeedStatusRes, String> {
+    let req = ProtoLiquidityFeedStatusReq {
+        payload_type: None,
+        liquidity_feed_id,
+    };
+    let message = inner_client
+        .request(req, ProtoCsPayloadType::ProtoLiquidityFeedStatusReq)
+        .await?;
+
+    let ManagerApiMessage::Response(ManagerApiResponse::LiquidityFeedStatusRes(res)) = message
+    else {
+        return Err(get_unexpected_response_error(&message));
+    };
+
+    Ok(res)
+}
+
+async fn get_server_time<T: ManagerApiCallbackHandler + Send + Sync + 'static>(
+    inner_client: &ManagerApiCallback<T>,
+) -> Result<i64, String> {
+    let req = ProtoServerTimeReq { payload_type: None };
+    let message = inner_client
+        .request(req, ProtoCsPayloadType::ProtoServerTimeReq)
+        .await?;
+
+    let ManagerApiMessage::Response(ManagerApiResponse::ServerTimeRes(res)) = message else {
+        return Err(get_unexpected_response_error(&message));
+    };
+
+    Ok(res.time_in_millis)
+}
+
+/// Requests all reference entities and replaces the cache with them.
+async fn load_reference_data<T: ManagerApiCallbackHandler + Send + Sync + 'static>(
+    inner_client: &ManagerApiCallback<T>,
+) -> Result<(), String> {
+    // the lists are independent, so they are requested concurrently to keep connect fast
+    let (countries, assets, asset_classes, categories) = tokio::try_join!(
+        inner_client.request(
+            ProtoCountryListReq { payload_type: None },
+            ProtoCsPayloadType::ProtoCountryListReq,
+        ),
+        inner_client.request(
+            ProtoAssetListReq { payload_type: None },
+            ProtoCsPayloadType::ProtoAssetListReq,
+        ),
+        inner_client.request(
+            ProtoAssetClassListReq { payload_type: None },
+            ProtoCsPayloadType::ProtoAssetClassListReq,
+        ),
+        inner_client.request(
+            ProtoSymbolCategoryListReq { payload_type: None },
+            ProtoCsPayloadType::ProtoSymbolCategoryListReq,
+        ),
+    )?;
+
+    let ManagerApiMessage::Response(ManagerApiResponse::CountryListRes(countries)) = countries
+    else {
+        return Err(get_unexpected_response_error(&countries));
+    };
+    let ManagerApiMessage::Response(ManagerApiResponse::AssetListRes(assets)) = assets else {
+        return Err(get_unexpected_response_error(&assets));
+    };
+    let ManagerApiMessage::Response(ManagerApiResponse::AssetClassListRes(asset_classes)) =
+        asset_classes
+    else {
+        return Err(get_unexpected_response_error(&asset_classes));
+    };
+    let ManagerApiMessage::Response(ManagerApiResponse::SymbolCategoryListRes(categories)) =
+        categories
+    else {
+        return Err(get_unexpected_response_error(&categories));
+    };
+
+    let reference_data = inner_client.get_reference_data();
+    let mut reference_data = reference_data.write().await;
+    reference_data.set_countries(countries.country);
+    reference_data.set_assets(assets.asset);
+    reference_data.set_asset_classes(asset_classes.asset_class);
+    reference_data.set_symbol_categories(categories.categories);
+
+    Ok(())
+}
+
+async fn measure_server_clock<T: ManagerApiCallbackHandler + Send + Sync + 'static>(
+    inner_client: &ManagerApiCallback<T>,
+) -> Result<ServerClock, String> {
+    let sent_at = Utc::now();
+    let server_time_millis = get_server_time(inner_client).await?;
+    let received_at = Utc::now();
+
+    Ok(ServerClock::from_sample(sent_at, received_at, server_time_millis))
+}
+
+async fn get_exposure<T: ManagerApiCallbackHandler + Send + Sync + 'static>(
+    inner_client: &ManagerApiCallback<T>,
+    book_type: ProtoBookRequestType,
+) -> Result<ExposureReport, String> {
+    let req = ProtoExposureSymbolListReq {
+        payload_type: None,
+        book_type: book_type as i32,
+    };
+    let message = inner_client
+        .request(req, ProtoCsPayloadType::ProtoExposureSymbolListReq)
+        .await?;
+
+    let ManagerApiMessage::Response(ManagerApiResponse::ExposureSymbolListRes(res)) = message else {
+        return Err(get_unexpected_response_error(&message));
+    };
+
+    Ok(ExposureReport::new(res.exposure_symbol_record))
+}
+
+/// Operations that bypass regular trading rules. Use them only to correct the book after
+/// a platform incident.
+#[cfg(feature = "dangerous-ops")]
+impl<T: ManagerApiCallbackHandler + Send + Sync + 'static> ManagerApiClient<T> {
+    /// Opens the position even when it is impossible to do it in a regular way
+    /// (e.g. LP stopped streaming). The reason is required and is appended to the deal comment.
+    /// Returns the execution events of the request up to the fill (or cancel); they are also
+    /// passed to the handler. A rejected request is returned as `Err`.
+    pub async fn force_open_position(
+        &self,
+        req: ProtoForceOpenPositionReq,
+        reason: &str,
+    ) -> Result<Vec<ProtoExecutionEvent>, String> {
+        let mut req = req;
+        req.comment = Some(get_force_comment(req.comment.take(), reason)?);
+
+        if req.channel.is_none() {
+            req.channel = Some("ManagerAPI".to_string());
+        }
+
+        if req.new_way.is_none() {
+            req.new_way = Some(true);
+        }
+
+        self.logger.write_warning(
+            "ManagerApiClient.force_open_position".into(),
+            format!("Force open position for trader {}: {}", req.trader_id, reason),
+            None,
+        );
+        let messages = self
+            .inner_client
+            .request_until(
+                req,
+                ProtoCsPayloadType::ProtoForceOpenPositionReq,
+                is_final_execution_event,
+            )
+            .await?;
+
+        get_force_execution_events(messages)
+    }
+
+    /// Closes the position even when it is impossible to do it in a regular way
+    /// (e.g. LP stopped streaming). The reason is required and is appended to the deal comment.
+    /// Returns the execution events of the request up to the fill (or cancel); they are also
+    /// passed to the handler. A rejected request is returned as `Err`.
+    pub async fn force_close_position(
+        &self,
+        req: ProtoForceClosePositionReq,
+        reason: &str,
+    ) -> Result<Vec<ProtoExecutionEvent>, String> {
+        let mut req = req;
+        req.comment = Some(get_force_comment(req.comment.take(), reason)?);
+
+        if req.channel.is_none() {
+            req.channel = Some("ManagerAPI".to_string());
+        }
+
+        self.logger.write_warning(
+            "ManagerApiClient.force_close_position".into(),
+            format!("Force close position {}: {}", req.position_id, reason),
+            None,
+        );
+        let messages = self
+            .inner_client
+            .request_until(
+                req,
+                ProtoCsPayloadType::ProtoForceClosePositionReq,
+                is_final_execution_event,
+            )
+            .await?;
+
+        get_force_execution_events(messages)
+    }
+}
+
+/// Keeps the comment of the caller and appends the reason to it.
+#[cfg(feature = "dangerous-ops")]
+fn get_force_comment(comment: Option<String>, reason: &str) -> Result<String, String> {
+    let reason = reason.trim();
+
+    if reason.is_empty() {
+        return Err("Reason is required for force operations".to_string());
+    }
+
+    match comment {
+        Some(comment) if !comment.trim().is_empty() => {
+            Ok(format!("{}; {}", comment.trim(), reason))
+        }
+        _ => Ok(reason.to_string()),
+    }
+}
+
+#[cfg(feature = "dangerous-ops")]
+fn is_final_execution_event(message: &ManagerApiMessage) -> bool {
+    let ManagerApiMessage::Event(ManagerApiEvent::ExecutionEvent(event)) = message else {
+        return true;
+    };
+
+    matches!(
+        ProtoExecutionType::try_from(event.execution_type),
+        Ok(ProtoExecutionType::OrderFilled)
+            | Ok(ProtoExecutionType::OrderCancelled)
+            | Ok(ProtoExecutionType::OrderExpired)
+            | Ok(ProtoExecutionType::OrderRejected)
+            | Ok(ProtoExecutionType::OrderCancelRejected)
+    )
+}
+
+#[cfg(feature = "dangerous-ops")]
+fn get_force_execution_events(
+    messages: Vec<ManagerApiMessage>,
+) -> Result<Vec<ProtoExecutionEvent>, String> {
+    let mut events = Vec::with_capacity(messages.len());
+
+    for message in messages {
+        let ManagerApiMessage::Event(ManagerApiEvent::ExecutionEvent(event)) = message else {
+            return Err(get_unexpected_response_error(&message));
+        };
+
+        if matches!(
+            ProtoExecutionType::try_from(event.execution_type),
+            Ok(ProtoExecutionType::OrderRejected) | Ok(ProtoExecutionType::OrderCancelRejected)
+        ) {
+            return Err(format!(
+                "Force operation rejected: {}",
+                event.error_code.as_deref().unwrap_or("unknown error")
+            ));
+        }
+
+        events.push(event);
+    }
+
+    Ok(events)
+}
+
+/// Checks the balance history of the trader for the Webservices client, which has no history
+/// endpoint. The login of the Webservices call is resolved to the trader id.
+#[async_trait::async_trait]
+impl<T: ManagerApiCallbackHandler + Send + Sync + 'static> BalanceChangeVerifier
+    for ManagerApiClient<T>
+{
+    async fn find_balance_change(
+        &self,
+        login: i64,
+        external_id: &str,
+        from_timestamp: i64,
+    ) -> Result<Option<i64>, String> {
+        let trader_id = self.get_trader_id(login).await?;
+        let change =
+            ManagerApiClient::find_balance_change(self, trader_id, external_id, from_timestamp)
+                .await?;
+
+        Ok(change.map(|change| change.balance_history_id))
+    }
+}
 
 pub struct ManagerApiConfigWrapper {
     pub config: Arc<dyn ManagerApiConfig + Send + Sync + 'static>,
     pub creds: Arc<dyn ManagerCreds + Send + Sync + 'static>,
+    endpoint_rotation: EndpointRotation,
+    relay: OnceCell<LocalRelay>,
+    logger: Arc<dyn Logger + Send + Sync + 'static>,
 }
 
 #[async_trait::async_trait]
@@ -122,27 +1844,267 @@ pub trait ManagerApiConfig {
     async fn get_url(&self) -> String;
     async fn get_plant_id(&self) -> String;
     async fn get_env_name(&self) -> String;
+
+    /// Ordered list of proxy endpoints: the primary one goes first and backups follow.
+    /// By default contains only the endpoint of `get_url`.
+    async fn get_endpoints(&self) -> Vec<ManagerApiEndpoint> {
+        vec![ManagerApiEndpoint::new(self.get_url().await)]
+    }
+
+    /// TLS with the default roots unless overridden.
+    async fn get_transport(&self) -> ManagerApiTransport {
+        ManagerApiTransport::default()
+    }
+
+    /// Outbound proxy for the tcp connection. Not used by default.
+    async fn get_proxy(&self) -> Option<ProxyConfig> {
+        None
+    }
 }
 
 impl ManagerApiConfigWrapper {
+    pub fn new(
+        config: Arc<dyn ManagerApiConfig + Send + Sync + 'static>,
+        creds: Arc<dyn ManagerCreds + Send + Sync + 'static>,
+        logger: Arc<dyn Logger + Send + Sync + 'static>,
+    ) -> Self {
+        Self {
+            config,
+            creds,
+            endpoint_rotation: EndpointRotation::default(),
+            relay: OnceCell::new(),
+            logger,
+        }
+    }
+
     pub async fn get_domain(&self) -> String {
-        let url = self.config.get_url().await;
-        let mut splits = url.split(':');
+        self.get_active_endpoint().await.get_server_name()
+    }
+
+    pub async fn get_endpoints(&self) -> Vec<ManagerApiEndpoint> {
+        let endpoints = self.config.get_endpoints().await;
+
+        if endpoints.is_empty() {
+            return vec![ManagerApiEndpoint::new(self.config.get_url().await)];
+        }
 
-        splits.next().unwrap().to_string()
+        endpoints
+    }
+
+    pub async fn get_active_endpoint(&self) -> ManagerApiEndpoint {
+        let endpoints = self.get_endpoints().await;
+        let index = self.endpoint_rotation.get_active_index(endpoints.len());
+
+        endpoints[index].clone()
+    }
+
+    pub async fn get_active_endpoint_index(&self) -> usize {
+        let endpoints_count = self.get_endpoints().await.len();
+
+        self.endpoint_rotation.get_active_index(endpoints_count)
+    }
+
+    /// Switches to the next endpoint and returns it. The next connection attempt uses it.
+    pub async fn rotate_endpoint(&self) -> ManagerApiEndpoint {
+        let endpoints = self.get_endpoints().await;
+        let index = self.endpoint_rotation.rotate(endpoints.len());
+
+        endpoints[index].clone()
+    }
+
+    pub fn reset_to_primary_endpoint(&self) {
+        self.endpoint_rotation.reset_to_primary();
+    }
+
+    pub(crate) async fn create_auth_req(&self) -> ProtoManagerAuthReq {
+        ProtoManagerAuthReq {
+            payload_type: Some(ProtoCsPayloadType::ProtoManagerAuthReq as i32),
+            plant_id: self.config.get_plant_id().await,
+            environment_name: self.config.get_env_name().await,
+            login: self.creds.get_login().await,
+            password_hash: generate_password_hash(&self.creds.get_password().await),
+        }
+    }
+
+    /// Connects to the endpoint the same way the client does (through the proxy and TLS) and
+    /// authenticates on a separate connection. Returns `Ok` only if the authentication is
+    /// confirmed, so a reachable port of a broken endpoint does not pass.
+    /// The probe logs on a second session with the same credentials while the main one is
+    /// active; the connection is closed right after the response.
+    pub async fn probe_endpoint(&self, endpoint: &ManagerApiEndpoint) -> Result<(), String> {
+        let transport = self.config.get_transport().await;
+        let tls = match &transport {
+            ManagerApiTransport::Tls(tls) => Some(RelayTls {
+                server_name: parse_server_name(&endpoint.get_server_name())?,
+                config: tls.build_client_config()?,
+            }),
+            ManagerApiTransport::Plaintext => None,
+        };
+        let target = RelayTarget {
+            host_port: endpoint.url.clone(),
+            proxy: self.config.get_proxy().await,
+            tls,
+        };
+        let auth_req = self.create_auth_req().await;
+
+        probe_endpoint(target, auth_req, Arc::clone(&self.logger)).await
+    }
+
+    /// Checks the endpoints and the transport, so a bad config fails before connecting.
+    pub async fn validate(&self) -> Result<(), String> {
+        let transport = self.config.get_transport().await;
+        transport.validate()?;
+
+        if let Some(proxy) = self.config.get_proxy().await {
+            proxy.validate()?;
+        }
+
+        for endpoint in self.get_endpoints().await {
+            parse_host_port(&endpoint.url)?;
+
+            if !transport.is_plaintext() {
+                parse_server_name(&endpoint.get_server_name())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Points the local relay to the endpoint and returns the address of the relay.
+    async fn get_relay_host_port(
+        &self,
+        endpoint: &ManagerApiEndpoint,
+        tls: Option<&ManagerApiTlsConfig>,
+        proxy: Option<ProxyConfig>,
+    ) -> Result<String, String> {
+        let relay = self
+            .relay
+            .get_or_try_init(|| LocalRelay::start(Arc::clone(&self.logger)))
+            .await?;
+        let tls = match tls {
+            Some(tls) => Some(RelayTls {
+                server_name: parse_server_name(&endpoint.get_server_name())?,
+                config: tls.build_client_config()?,
+            }),
+            None => None,
+        };
+        let target = RelayTarget {
+            host_port: endpoint.url.clone(),
+            proxy,
+            tls,
+        };
+        relay.set_target(target).await;
+
+        Ok(relay.get_local_addr())
     }
 }
 
 #[async_trait::async_trait]
 impl TcpClientSocketSettings for ManagerApiConfigWrapper {
     async fn get_host_port(&self) -> Option<String> {
-        let url = self.config.get_url().await;
-        Some(url)
+        let endpoint = self.get_active_endpoint().await;
+        let transport = self.config.get_transport().await;
+
+        let proxy = self.config.get_proxy().await;
+        let custom_tls = transport.get_custom_tls();
+
+        // the tcp client can not use custom certificates and proxies, so they are handled
+        // by the relay
+        if custom_tls.is_some() || proxy.is_some() {
+            return match self.get_relay_host_port(&endpoint, custom_tls, proxy).await {
+                Ok(host_port) => Some(host_port),
+                Err(err) => {
+                    self.logger.write_warning(
+                        "ManagerApiConfigWrapper.get_host_port".into(),
+                        redact(&format!("Failed to set up local relay: {}", err)),
+                        None,
+                    );
+                    None
+                }
+            };
+        }
+
+        Some(endpoint.url)
     }
 
     async fn get_tls_settings(&self) -> Option<TlsSettings> {
+        let transport = self.config.get_transport().await;
+
+        if transport.is_plaintext() || transport.get_custom_tls().is_some() {
+            return None;
+        }
+
         Some(TlsSettings {
             server_name: self.get_domain().await,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::api_client::{find_in_history_pages, get_next_page_window, HistoryPage};
+    use std::sync::Mutex;
+
+    const PAGE_SIZE: usize = 2;
+
+    /// Serves the history in pages of `PAGE_SIZE`, returns the found entity and the requested
+    /// windows.
+    async fn find_in_mocked_history(
+        history: &[i64],
+        is_descending: bool,
+        target: i64,
+    ) -> (Option<i64>, Vec<(i64, i64)>) {
+        let windows = Mutex::new(vec![]);
+        let found = find_in_history_pages(
+            0,
+            100,
+            |from_timestamp, to_timestamp| {
+                windows.lock().unwrap().push((from_timestamp, to_timestamp));
+                let mut entities: Vec<i64> = history
+                    .iter()
+                    .copied()
+                    .filter(|timestamp| (from_timestamp..=to_timestamp).contains(timestamp))
+                    .collect();
+
+                if is_descending {
+                    entities.reverse();
+                }
+
+                let has_more = entities.len() > PAGE_SIZE;
+                entities.truncate(PAGE_SIZE);
+
+                async move { Ok(HistoryPage { entities, has_more }) }
+            },
+            |timestamp| Some(*timestamp),
+            |timestamp| *timestamp == target,
+        )
+        .await
+        .unwrap();
+
+        (found, windows.into_inner().unwrap())
+    }
+
+    #[tokio::test]
+    async fn finds_entity_on_later_pages_in_both_orders() {
+        let history = [10, 20, 30, 40, 50];
+
+        let (found, windows) = find_in_mocked_history(&history, true, 10).await;
+        assert_eq!(found, Some(10));
+        assert_eq!(windows, [(0, 100), (0, 39), (0, 19)]);
+
+        let (found, windows) = find_in_mocked_history(&history, false, 50).await;
+        assert_eq!(found, Some(50));
+        assert_eq!(windows, [(0, 100), (21, 100), (41, 100)]);
+
+        let (found, windows) = find_in_mocked_history(&history, true, 15).await;
+        assert_eq!(found, None);
+        assert_eq!(windows.len(), 3);
+    }
+
+    #[test]
+    fn stops_when_window_can_not_be_narrowed() {
+        assert_eq!(get_next_page_window(&[], 0, 100), None);
+        assert_eq!(get_next_page_window(&[0], 0, 100), None);
+        assert_eq!(get_next_page_window(&[100, 50], 0, 100), Some((0, 49)));
+    }
+}
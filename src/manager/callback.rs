@@ -1,34 +1,231 @@
 use crate::manager::api_client::ManagerApiConfigWrapper;
 use crate::manager::common_messages_external::ProtoMessage;
-use crate::manager::cs_messages_external::{ProtoCsPayloadType, ProtoManagerAuthReq};
-use crate::manager::models::ManagerApiMessage;
+use crate::manager::cs_messages_external::{ProtoCsPayloadType, ProtoExecutionEvent};
+use crate::manager::event_bus::{EventTrader, ManagerApiEventBus, OverflowPolicy};
+use crate::manager::models::{ManagerApiEvent, ManagerApiMessage, ManagerApiResponse};
+use crate::manager::options::ManagerApiClientOptions;
+use crate::manager::reference_data::ReferenceData;
 use crate::manager::serialization::{ManagerApiSerializer, ManagerApiSerializerState};
-use crate::utils::generate_password_hash;
+use crate::manager::sessions::TraderSessionRegistry;
+use crate::manager::shutdown::{ManagerApiShutdownReport, UnconfirmedRequest};
+use crate::metrics::ConnectorMetrics;
+use crate::rate_limiter::{RateLimiter, RequestPriority, MANAGER_API_GROUP};
+use crate::redaction::redact;
+use crate::telemetry::RequestSpan;
 use my_tcp_sockets::tcp_connection::TcpSocketConnection;
 use my_tcp_sockets::SocketEventCallback;
 use rust_extensions::Logger;
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, oneshot, watch, Mutex, Notify, RwLock};
 
 const PROCESS: &str = "ManagerApiCallback";
 
+/// The handler is called by a dedicated task one call at a time, in the order the messages
+/// are received, so a slow handler delays the next calls but not reading of the socket until
+/// the handler queue is full (see `ManagerApiClientOptions::handler_overflow_policy`).
 #[async_trait::async_trait]
 pub trait ManagerApiCallbackHandler {
     async fn on_connected(&self);
     async fn on_disconnected(&self);
+    /// Called for every decoded message, including responses to `request` calls: the
+    /// awaiting caller gets its own copy of the response.
     async fn on_message(&self, message: ManagerApiMessage);
+
+    /// Called before `on_message` for execution events flagged with `notifyBigVolume`
+    /// according to the Trade Notification Profile.
+    async fn on_big_volume_execution(&self, _event: &ProtoExecutionEvent) {}
+
+    /// Called when the reconnect backoff is exhausted. The client does not reconnect anymore
+    /// until `connect` is called again.
+    async fn on_reconnect_gave_up(&self, _attempts: u32) {}
 }
 
 pub type ManagerApiConnection =
     TcpSocketConnection<ProtoMessage, ManagerApiSerializer, ManagerApiSerializerState>;
 
+/// Call of the handler, made by the handler task in the order the calls are queued.
+enum HandlerCall {
+    Connected,
+    Disconnected,
+    /// The message with the resolved trader of the event, used by the event bus filters.
+    Message(Box<ManagerApiMessage>, EventTrader),
+    ReconnectGaveUp(u32),
+}
+
+impl HandlerCall {
+    fn is_message(&self) -> bool {
+        matches!(self, HandlerCall::Message(..))
+    }
+}
+
+#[derive(Default)]
+struct HandlerQueueState {
+    calls: VecDeque<HandlerCall>,
+    messages_count: usize,
+    is_closed: bool,
+}
+
+/// Bounded queue of the handler calls. Only messages count against the capacity and are
+/// dropped on overflow: connection calls are always queued, so the handler sees every
+/// connect and disconnect.
+struct HandlerQueue {
+    state: std::sync::Mutex<HandlerQueueState>,
+    capacity: usize,
+    call_notify: Notify,
+    space_notify: Notify,
+}
+
+impl HandlerQueue {
+    fn new(capacity: usize) -> Self {
+        Self {
+            state: std::sync::Mutex::new(HandlerQueueState::default()),
+            capacity: capacity.max(1),
+            call_notify: Notify::new(),
+            space_notify: Notify::new(),
+        }
+    }
+
+    fn close(&self) {
+        self.state.lock().unwrap().is_closed = true;
+        self.call_notify.notify_one();
+        self.space_notify.notify_one();
+    }
+
+    /// Returns `None` when the queue is closed and empty.
+    async fn recv(&self) -> Option<HandlerCall> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+
+                if let Some(call) = state.calls.pop_front() {
+                    if call.is_message() {
+                        state.messages_count -= 1;
+                        self.space_notify.notify_one();
+                    }
+
+                    return Some(call);
+                }
+
+                if state.is_closed {
+                    return None;
+                }
+            }
+
+            self.call_notify.notified().await;
+        }
+    }
+
+    /// Returns the count of messages dropped by the overflow policy.
+    async fn push(&self, call: HandlerCall, policy: OverflowPolicy) -> usize {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+
+                if state.is_closed {
+                    return 0;
+                }
+
+                if !call.is_message() || state.messages_count < self.capacity {
+                    state.messages_count += call.is_message() as usize;
+                    state.calls.push_back(call);
+                    self.call_notify.notify_one();
+                    return 0;
+                }
+
+                match policy {
+                    OverflowPolicy::Block => {}
+                    OverflowPolicy::DropOldest => {
+                        if let Some(index) = state.calls.iter().position(HandlerCall::is_message) {
+                            state.calls.remove(index);
+                        }
+
+                        state.calls.push_back(call);
+                        self.call_notify.notify_one();
+                        return 1;
+                    }
+                    OverflowPolicy::Disconnect => {
+                        state.calls.retain(|call| !call.is_message());
+                        let dropped = state.messages_count + 1;
+                        state.messages_count = 0;
+                        return dropped;
+                    }
+                }
+            }
+
+            self.space_notify.notified().await;
+        }
+    }
+}
+
+/// Closes the handler queue when the last clone of the callback is dropped, so the handler
+/// task ends after the queued calls.
+struct HandlerQueueHandle(Arc<HandlerQueue>);
+
+impl Drop for HandlerQueueHandle {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+type ResponseResult = Result<ManagerApiMessage, String>;
+
+/// Receives messages with the clientMsgId of the request.
+enum ResponseSender {
+    /// Completed by the first message, used by `request`.
+    Single(oneshot::Sender<ResponseResult>),
+    /// Receives every message until the caller removes the request, used by `request_until`.
+    Stream(mpsc::UnboundedSender<ResponseResult>),
+}
+
+/// Sent request that waits for a message with the same clientMsgId. Requests without
+/// a sender are sent by `send`: nobody waits for their responses.
+struct PendingRequest {
+    sender: Option<ResponseSender>,
+    /// Span of a request sent by `send`, finished when the response comes or times out.
+    span: Option<RequestSpan>,
+    payload_type: ProtoCsPayloadType,
+    sent_at: Instant,
+}
+
+type PendingRequests = HashMap<String, PendingRequest>;
+
+/// Counts the request from the shutdown check until its frame is passed to the connection.
+struct QueuedSendGuard<'a> {
+    queued_sends: &'a AtomicUsize,
+}
+
+impl Drop for QueuedSendGuard<'_> {
+    fn drop(&mut self) {
+        self.queued_sends.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 pub struct ManagerApiCallback<T: ManagerApiCallbackHandler + Send + Sync + 'static> {
     handler: Arc<T>,
     config_wrapper: Arc<ManagerApiConfigWrapper>,
     connection: Arc<RwLock<Option<Arc<ManagerApiConnection>>>>,
-    wait_timeout: Duration,
+    pending_requests: Arc<Mutex<PendingRequests>>,
+    reference_data: Arc<RwLock<ReferenceData>>,
+    trader_sessions: Arc<RwLock<TraderSessionRegistry>>,
+    event_bus: ManagerApiEventBus,
+    handler_queue: Arc<OnceLock<HandlerQueueHandle>>,
+    metrics: Arc<ConnectorMetrics>,
+    /// Count of lost connections.
+    disconnects: Arc<watch::Sender<u64>>,
+    /// Count of confirmed authorizations.
+    authorizations: Arc<watch::Sender<u64>>,
+    is_authorized: Arc<AtomicBool>,
+    /// Login of the last connection, cached for the request spans.
+    login: Arc<AtomicI64>,
+    is_shutting_down: Arc<AtomicBool>,
+    /// Count of accepted requests which got a token of the rate limiter and wait for
+    /// the connection.
+    queued_sends: Arc<AtomicUsize>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    options: ManagerApiClientOptions,
     logger: Arc<dyn Logger + Send + Sync + 'static>,
 }
 
@@ -38,7 +235,20 @@ impl<T: ManagerApiCallbackHandler + Send + Sync + 'static> Clone for ManagerApiC
             handler: self.handler.clone(),
             config_wrapper: self.config_wrapper.clone(),
             connection: self.connection.clone(),
-            wait_timeout: self.wait_timeout.clone(),
+            pending_requests: self.pending_requests.clone(),
+            reference_data: self.reference_data.clone(),
+            trader_sessions: self.trader_sessions.clone(),
+            event_bus: self.event_bus.clone(),
+            handler_queue: self.handler_queue.clone(),
+            metrics: self.metrics.clone(),
+            disconnects: self.disconnects.clone(),
+            authorizations: self.authorizations.clone(),
+            is_authorized: self.is_authorized.clone(),
+            login: self.login.clone(),
+            is_shutting_down: self.is_shutting_down.clone(),
+            queued_sends: self.queued_sends.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            options: self.options.clone(),
             logger: self.logger.clone(),
         }
     }
@@ -48,46 +258,488 @@ impl<T: ManagerApiCallbackHandler + Send + Sync + 'static> ManagerApiCallback<T>
     pub fn new(
         handler: Arc<T>,
         config: Arc<ManagerApiConfigWrapper>,
-        wait_timeout: Duration,
+        options: ManagerApiClientOptions,
         logger: Arc<dyn Logger + Send + Sync + 'static>,
     ) -> Self {
         ManagerApiCallback {
             handler,
             config_wrapper: config,
             connection: Arc::new(RwLock::new(None)),
-            wait_timeout,
+            pending_requests: Arc::new(Mutex::new(HashMap::new())),
+            reference_data: Arc::new(RwLock::new(ReferenceData::default())),
+            trader_sessions: Arc::new(RwLock::new(TraderSessionRegistry::default())),
+            event_bus: ManagerApiEventBus::default(),
+            handler_queue: Arc::new(OnceLock::new()),
+            metrics: Arc::new(ConnectorMetrics::default()),
+            disconnects: Arc::new(watch::Sender::new(0)),
+            authorizations: Arc::new(watch::Sender::new(0)),
+            is_authorized: Arc::new(AtomicBool::new(false)),
+            login: Arc::new(AtomicI64::new(0)),
+            is_shutting_down: Arc::new(AtomicBool::new(false)),
+            queued_sends: Arc::new(AtomicUsize::new(0)),
+            rate_limiter: None,
+            options,
             logger,
         }
     }
 
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Reference data that is kept current from the changed and deleted events.
+    pub fn get_reference_data(&self) -> Arc<RwLock<ReferenceData>> {
+        Arc::clone(&self.reference_data)
+    }
+
+    /// Trader sessions that are tracked from the logon and logout events.
+    pub fn get_trader_sessions(&self) -> Arc<RwLock<TraderSessionRegistry>> {
+        Arc::clone(&self.trader_sessions)
+    }
+
+    /// Bus that delivers all received messages to subscribers, including responses to
+    /// `request` calls. Messages go through the handler queue first, so the ones dropped by
+    /// its overflow policy are not published.
+    pub fn get_event_bus(&self) -> &ManagerApiEventBus {
+        &self.event_bus
+    }
+
+    pub fn get_metrics(&self) -> Arc<ConnectorMetrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Receives the count of lost connections. Unlike `Notify`, a disconnect that happens
+    /// while the receiver is not awaiting is not lost: `changed` returns at once.
+    pub fn subscribe_disconnects(&self) -> watch::Receiver<u64> {
+        self.disconnects.subscribe()
+    }
+
+    /// Receives the count of confirmed authorizations, e.g. to reload caches after reconnects.
+    pub fn subscribe_authorizations(&self) -> watch::Receiver<u64> {
+        self.authorizations.subscribe()
+    }
+
+    pub async fn on_reconnect_gave_up(&self, attempts: u32) {
+        self.call_handler(HandlerCall::ReconnectGaveUp(attempts))
+            .await;
+    }
+
+    /// Queues the call for the handler task, so a slow handler or a blocked subscriber
+    /// does not stall reading of the socket until the queue is full, see
+    /// `handler_overflow_policy`. The task is started by the first call.
+    async fn call_handler(&self, call: HandlerCall) {
+        let queue = self.handler_queue.get_or_init(|| {
+            let queue = Arc::new(HandlerQueue::new(self.options.handler_queue_capacity));
+            let receiver = Arc::clone(&queue);
+            let handler = Arc::clone(&self.handler);
+            let event_bus = self.event_bus.clone();
+
+            tokio::spawn(async move {
+                while let Some(call) = receiver.recv().await {
+                    match call {
+                        HandlerCall::Connected => handler.on_connected().await,
+                        HandlerCall::Disconnected => handler.on_disconnected().await,
+                        HandlerCall::Message(message, trader) => {
+                            let message = *message;
+
+                            if let ManagerApiMessage::Event(ManagerApiEvent::ExecutionEvent(
+                                event,
+                            )) = &message
+                            {
+                                if event.notify_big_volume == Some(true) {
+                                    handler.on_big_volume_execution(event).await;
+                                }
+                            }
+
+                            event_bus.publish_for_trader(&message, &trader).await;
+                            handler.on_message(message).await;
+                        }
+                        HandlerCall::ReconnectGaveUp(attempts) => {
+                            handler.on_reconnect_gave_up(attempts).await
+                        }
+                    }
+                }
+            });
+
+            HandlerQueueHandle(queue)
+        });
+
+        let policy = self.options.handler_overflow_policy;
+        let dropped = queue.0.push(call, policy).await;
+
+        if dropped == 0 {
+            return;
+        }
+
+        self.metrics.on_handler_messages_dropped(dropped);
+
+        if policy == OverflowPolicy::Disconnect {
+            self.logger.write_warning(
+                PROCESS.into(),
+                format!(
+                    "Handler queue is full. Dropped {} messages and the connection",
+                    dropped
+                ),
+                None,
+            );
+            self.drop_connection().await;
+        }
+    }
+
+    /// Closes the current connection. The tcp client connects again to the active endpoint.
+    pub async fn drop_connection(&self) {
+        let connection = self.connection.read().await.clone();
+
+        if let Some(connection) = connection {
+            connection.disconnect().await;
+        }
+    }
+
+    /// Stops accepting new requests, sends the queued ones and waits until the sent ones are
+    /// confirmed, the connection is lost or the deadline passes. A request of `send` is
+    /// awaited no longer than `wait_timeout` since it was sent: the server does not answer
+    /// every request.
+    pub async fn shutdown(&self, deadline: Duration) -> ManagerApiShutdownReport {
+        self.is_shutting_down.store(true, Ordering::SeqCst);
+        let instant = Instant::now();
+        let mut is_disconnected_early = false;
+
+        loop {
+            if self.queued_sends.load(Ordering::SeqCst) == 0 && !self.has_awaited_requests().await {
+                break;
+            }
+
+            if !self.is_connected().await {
+                is_disconnected_early = true;
+                break;
+            }
+
+            if instant.elapsed() >= deadline {
+                break;
+            }
+
+            tokio::time::sleep(self.options.connect_poll_interval).await;
+        }
+
+        let unconfirmed_requests = self
+            .pending_requests
+            .lock()
+            .await
+            .iter()
+            .map(|(client_msg_id, request)| UnconfirmedRequest {
+                client_msg_id: client_msg_id.clone(),
+                payload_type: request.payload_type,
+                elapsed: request.sent_at.elapsed(),
+            })
+            .collect();
+
+        ManagerApiShutdownReport {
+            unconfirmed_requests,
+            queued_requests: self.queued_sends.load(Ordering::SeqCst),
+            is_disconnected_early,
+        }
+    }
+
+    async fn has_awaited_requests(&self) -> bool {
+        let wait_timeout = self.options.wait_timeout;
+
+        self.pending_requests
+            .lock()
+            .await
+            .values()
+            .any(|request| request.sender.is_some() || request.sent_at.elapsed() < wait_timeout)
+    }
+
+    /// Accepts new requests again after `shutdown`.
+    pub fn resume(&self) {
+        self.is_shutting_down.store(false, Ordering::SeqCst);
+    }
+
+    fn get_login(&self) -> i64 {
+        self.login.load(Ordering::SeqCst)
+    }
+
     pub async fn is_connected(&self) -> bool {
         self.connection.read().await.is_some()
     }
 
+    /// Returns TRUE when the connection is established and the authorization is confirmed.
+    pub async fn is_authorized(&self) -> bool {
+        self.is_connected().await && self.is_authorized.load(Ordering::SeqCst)
+    }
+
+    /// Waits until the connection is authorized. A connected socket is not enough: through
+    /// the local relay the socket connects to loopback even when the endpoint is down.
     pub async fn wait_until_connected(&self) -> Result<(), String> {
         let instant = Instant::now();
 
         loop {
-            if self.is_connected().await {
+            if self.is_authorized().await {
                 // ensure full initialization
-                tokio::time::sleep(Duration::from_millis(500)).await;
+                tokio::time::sleep(self.options.settle_delay).await;
                 return Ok(());
             }
 
-            tokio::time::sleep(Duration::from_millis(250)).await;
+            tokio::time::sleep(self.options.connect_poll_interval).await;
 
-            if instant.elapsed() > self.wait_timeout {
+            if instant.elapsed() > self.options.wait_timeout {
                 return Err("Connect timeout".to_string());
             }
         }
     }
 
+    /// Sends the request without waiting for the response. The response is passed to the handler.
+    /// The request is tracked until the response comes (or `wait_timeout` passes), so it is
+    /// reported by `shutdown` if it is not confirmed.
     pub async fn send<R: prost::Message>(
         &self,
         req: R,
         payload_type: ProtoCsPayloadType,
     ) -> Result<(), String> {
-        while !self.is_connected().await {
+        let client_msg_id = uuid::Uuid::new_v4().to_string();
+        let span = RequestSpan::manager(payload_type, &client_msg_id, self.get_login());
+        // the span is kept by the pending request until the response comes
+        let sending = span.instrument(self.send_with_client_msg_id(
+            req,
+            payload_type,
+            Some(client_msg_id.clone()),
+        ));
+        let mut pending_requests = self.pending_requests.lock().await;
+        let wait_timeout = self.options.wait_timeout;
+        let expired_ids: Vec<String> = pending_requests
+            .iter()
+            .filter(|(_, request)| {
+                request.sender.is_none() && request.sent_at.elapsed() >= wait_timeout
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in expired_ids {
+            let request = pending_requests.remove(&id);
+
+            if let Some(span) = request.and_then(|request| request.span) {
+                span.finish("timeout");
+            }
+        }
+
+        pending_requests.insert(
+            client_msg_id.clone(),
+            PendingRequest {
+                sender: None,
+                span: Some(span),
+                payload_type,
+                sent_at: Instant::now(),
+            },
+        );
+        drop(pending_requests);
+
+        let result = sending.await;
+
+        if result.is_err() {
+            let request = self.pending_requests.lock().await.remove(&client_msg_id);
+
+            if let Some(span) = request.and_then(|request| request.span) {
+                span.finish("not_sent");
+            }
+        }
+
+        result
+    }
+
+    /// Sends the request and waits for the response with the same clientMsgId.
+    /// The response is returned to the caller and is also passed to the registries, the
+    /// event bus and the handler like any other message. An error response or a response
+    /// that can not be decoded is returned as `Err`.
+    pub async fn request<R: prost::Message>(
+        &self,
+        req: R,
+        payload_type: ProtoCsPayloadType,
+    ) -> Result<ManagerApiMessage, String> {
+        let client_msg_id = uuid::Uuid::new_v4().to_string();
+        let span = RequestSpan::manager(payload_type, &client_msg_id, self.get_login());
+        let (sender, receiver) = oneshot::channel();
+        self.pending_requests.lock().await.insert(
+            client_msg_id.clone(),
+            PendingRequest {
+                sender: Some(ResponseSender::Single(sender)),
+                span: None,
+                payload_type,
+                sent_at: Instant::now(),
+            },
+        );
+
+        let result = span
+            .instrument(self.send_with_client_msg_id(
+                req,
+                payload_type,
+                Some(client_msg_id.clone()),
+            ))
+            .await;
+
+        if let Err(err) = result {
+            self.pending_requests.lock().await.remove(&client_msg_id);
+            span.finish("not_sent");
+            return Err(err);
+        }
+
+        let result = span
+            .instrument(tokio::time::timeout(self.options.wait_timeout, receiver))
+            .await;
+
+        let Ok(result) = result else {
+            self.pending_requests.lock().await.remove(&client_msg_id);
+            span.finish("timeout");
+            return Err(format!(
+                "Request timeout: {}",
+                payload_type.as_str_name()
+            ));
+        };
+
+        match result {
+            Ok(Ok(ManagerApiMessage::Error(err))) => {
+                span.finish("error");
+                Err(redact(&format!("Error response: {:?}", err)))
+            }
+            Ok(Ok(message)) => {
+                span.finish("ok");
+                Ok(message)
+            }
+            Ok(Err(err)) => {
+                span.finish("decode_failed");
+                Err(redact(&format!("Failed to decode response: {}", err)))
+            }
+            Err(_) => {
+                span.finish("disconnected");
+                Err(format!(
+                    "Disconnected before response: {}",
+                    payload_type.as_str_name()
+                ))
+            }
+        }
+    }
+
+    /// Sends the request and collects all messages with the same clientMsgId until
+    /// `is_last` returns true for one of them. Like with `request`, every message is also
+    /// passed to the registries, the event bus and the handler. An error response or a
+    /// message that can not be decoded is returned as `Err`. `wait_timeout` applies to the
+    /// whole sequence.
+    pub async fn request_until<R: prost::Message>(
+        &self,
+        req: R,
+        payload_type: ProtoCsPayloadType,
+        is_last: impl Fn(&ManagerApiMessage) -> bool,
+    ) -> Result<Vec<ManagerApiMessage>, String> {
+        let client_msg_id = uuid::Uuid::new_v4().to_string();
+        let span = RequestSpan::manager(payload_type, &client_msg_id, self.get_login());
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        self.pending_requests.lock().await.insert(
+            client_msg_id.clone(),
+            PendingRequest {
+                sender: Some(ResponseSender::Stream(sender)),
+                span: None,
+                payload_type,
+                sent_at: Instant::now(),
+            },
+        );
+
+        let result = span
+            .instrument(self.send_with_client_msg_id(
+                req,
+                payload_type,
+                Some(client_msg_id.clone()),
+            ))
+            .await;
+
+        if let Err(err) = result {
+            self.pending_requests.lock().await.remove(&client_msg_id);
+            span.finish("not_sent");
+            return Err(err);
+        }
+
+        let mut messages = vec![];
+        let receiving = tokio::time::timeout(self.options.wait_timeout, async {
+            loop {
+                match receiver.recv().await {
+                    Some(Ok(ManagerApiMessage::Error(err))) => {
+                        return Err(("error", redact(&format!("Error response: {:?}", err))));
+                    }
+                    Some(Ok(message)) => {
+                        let is_last = is_last(&message);
+                        messages.push(message);
+
+                        if is_last {
+                            return Ok(());
+                        }
+                    }
+                    Some(Err(err)) => {
+                        return Err((
+                            "decode_failed",
+                            redact(&format!("Failed to decode response: {}", err)),
+                        ));
+                    }
+                    None => {
+                        return Err((
+                            "disconnected",
+                            format!(
+                                "Disconnected before response: {}",
+                                payload_type.as_str_name()
+                            ),
+                        ));
+                    }
+                }
+            }
+        });
+        let result = span.instrument(receiving).await;
+        self.pending_requests.lock().await.remove(&client_msg_id);
+
+        match result {
+            Ok(Ok(())) => {
+                span.finish("ok");
+                Ok(messages)
+            }
+            Ok(Err((status, err))) => {
+                span.finish(status);
+                Err(err)
+            }
+            Err(_) => {
+                span.finish("timeout");
+                Err(format!("Request timeout: {}", payload_type.as_str_name()))
+            }
+        }
+    }
+
+    async fn send_with_client_msg_id<R: prost::Message>(
+        &self,
+        req: R,
+        payload_type: ProtoCsPayloadType,
+        client_msg_id: Option<String>,
+    ) -> Result<(), String> {
+        let get_shutting_down_error =
+            || format!("Client is shutting down: {}", payload_type.as_str_name());
+
+        if self.is_shutting_down.load(Ordering::SeqCst) {
+            return Err(get_shutting_down_error());
+        }
+
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter
+                .acquire(MANAGER_API_GROUP, get_request_priority(payload_type))
+                .await;
+        }
+
+        // counted before the check, so `shutdown` either waits for the request or the request
+        // sees that the shutdown has started while it was waiting for the token
+        self.queued_sends.fetch_add(1, Ordering::SeqCst);
+        let _queued_send = QueuedSendGuard {
+            queued_sends: &self.queued_sends,
+        };
+
+        if self.is_shutting_down.load(Ordering::SeqCst) {
+            return Err(get_shutting_down_error());
+        }
+
+        while !self.is_authorized().await {
             self.wait_until_connected().await?;
         }
 
@@ -95,17 +747,60 @@ impl<T: ManagerApiCallbackHandler + Send + Sync + 'static> ManagerApiCallback<T>
         let connection = connection_lock.as_ref().expect("must exist");
         let message = ProtoMessage::new(req, payload_type);
 
-        let Ok(message) = message else {
+        let Ok(mut message) = message else {
             return Err(format!(
                 "Failed to create proto message: {:?}",
                 message.unwrap_err()
             ));
         };
 
+        message.client_msg_id = client_msg_id;
         connection.send(&message);
 
         Ok(())
     }
+
+    /// Completes the pending request with the same clientMsgId. A frame that can not be
+    /// decoded fails the request instead of leaving the caller waiting for the timeout.
+    async fn try_complete_request(
+        &self,
+        client_msg_id: Option<String>,
+        result: Result<ManagerApiMessage, String>,
+    ) {
+        let Some(client_msg_id) = client_msg_id else {
+            return;
+        };
+
+        let mut pending_requests = self.pending_requests.lock().await;
+
+        if let Some(PendingRequest {
+            sender: Some(ResponseSender::Stream(sender)),
+            ..
+        }) = pending_requests.get(&client_msg_id)
+        {
+            let _ = sender.send(result);
+            return;
+        }
+
+        let request = pending_requests.remove(&client_msg_id);
+        drop(pending_requests);
+
+        let Some(request) = request else {
+            return;
+        };
+
+        if let Some(span) = request.span {
+            span.finish(match &result {
+                Ok(ManagerApiMessage::Error(_)) => "error",
+                Ok(_) => "ok",
+                Err(_) => "decode_failed",
+            });
+        }
+
+        if let Some(ResponseSender::Single(sender)) = request.sender {
+            let _ = sender.send(result);
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -117,13 +812,8 @@ impl<T: ManagerApiCallbackHandler + Send + Sync + 'static>
         let log_ctx = Some(HashMap::from([("ConnectionId".to_string(), connection.id.to_string())]));
         self.logger
             .write_debug_info(PROCESS.into(), "Connected 1: received".into(), log_ctx.clone());
-        let req = ProtoManagerAuthReq {
-            payload_type: Some(ProtoCsPayloadType::ProtoManagerAuthReq as i32),
-            plant_id: self.config_wrapper.config.get_plant_id().await,
-            environment_name: self.config_wrapper.config.get_env_name().await,
-            login: self.config_wrapper.creds.get_login().await,
-            password_hash: generate_password_hash(&self.config_wrapper.creds.get_password().await),
-        };
+        let req = self.config_wrapper.create_auth_req().await;
+        self.login.store(req.login, Ordering::SeqCst);
         let mut bytes = vec![];
         prost::Message::encode(&req, &mut bytes).unwrap();
         let message = ProtoMessage {
@@ -134,13 +824,15 @@ impl<T: ManagerApiCallbackHandler + Send + Sync + 'static>
         self.logger
             .write_debug_info(PROCESS.into(), "Connected 2: sending auth".into(), log_ctx.clone());
 
+        self.is_authorized.store(false, Ordering::SeqCst);
         connection.send(&message);
         let mut current_connection = self.connection.write().await;
         *current_connection = Some(connection.clone());
 
         drop(current_connection);
+        self.metrics.on_connected();
 
-        self.handler.on_connected().await;
+        self.call_handler(HandlerCall::Connected).await;
 
         self.logger
             .write_debug_info(PROCESS.into(), "Connected 3: finished".into(), log_ctx);
@@ -154,26 +846,171 @@ impl<T: ManagerApiCallbackHandler + Send + Sync + 'static>
         *current_connection = None;
 
         drop(current_connection);
+        // dropping senders fails all requests that are waiting for a response,
+        // the sent ones stay unconfirmed
+        self.pending_requests
+            .lock()
+            .await
+            .retain(|_, request| request.sender.is_none());
+        self.metrics.on_disconnected();
+        self.trader_sessions.write().await.clear_online_sessions();
 
-        self.handler.on_disconnected().await;
+        if !self.is_authorized.load(Ordering::SeqCst)
+            && self.config_wrapper.get_endpoints().await.len() > 1
+        {
+            let endpoint = self.config_wrapper.rotate_endpoint().await;
+            self.logger.write_warning(
+                PROCESS.into(),
+                format!(
+                    "Disconnected before authorization. Switched to endpoint {}",
+                    endpoint.url
+                ),
+                log_ctx.clone(),
+            );
+        }
+
+        self.call_handler(HandlerCall::Disconnected).await;
+        self.disconnects.send_modify(|count| *count += 1);
         self.logger
             .write_debug_info(PROCESS.into(), "Disconnected: finished".into(), log_ctx);
     }
 
-    async fn payload(&mut self, _connection: &Arc<ManagerApiConnection>, contract: ProtoMessage) {
+    async fn payload(&mut self, connection: &Arc<ManagerApiConnection>, contract: ProtoMessage) {
         self.logger
             .write_debug_info(PROCESS.into(), "Payload received".into(), None);
+        let client_msg_id = contract.client_msg_id.clone();
+        let payload_type = contract.payload_type;
         let message = ManagerApiMessage::try_from_proto(contract);
 
         match message {
             Ok(Some(msg)) => {
-                self.handler.on_message(msg).await;
+                if let ManagerApiMessage::Response(ManagerApiResponse::ManagerAuthRes(_)) = &msg {
+                    self.is_authorized.store(true, Ordering::SeqCst);
+                    self.authorizations.send_modify(|count| *count += 1);
+                }
+
+                // the auth request has no client_msg_id, so its rejection has none either.
+                // Disconnecting lets `disconnected` switch to the next endpoint
+                if let ManagerApiMessage::Error(err) = &msg {
+                    if client_msg_id.is_none() && !self.is_authorized.load(Ordering::SeqCst) {
+                        self.logger.write_warning(
+                            PROCESS.into(),
+                            redact(&format!("Authentication rejected: {:?}", err)),
+                            None,
+                        );
+                        connection.disconnect().await;
+                    }
+                }
+
+                self.try_complete_request(client_msg_id, Ok(msg.clone()))
+                    .await;
+
+                if let ManagerApiMessage::Event(event) = &msg {
+                    self.reference_data.write().await.update(event);
+                    self.trader_sessions.write().await.update(event);
+                }
+
+                if let ManagerApiMessage::Response(ManagerApiResponse::TraderListRes(res)) = &msg {
+                    self.trader_sessions.write().await.set_traders(&res.trader);
+                }
+
+                let trader = match &msg {
+                    ManagerApiMessage::Event(event) => {
+                        self.trader_sessions.read().await.resolve_trader(event)
+                    }
+                    _ => EventTrader::default(),
+                };
+
+                self.call_handler(HandlerCall::Message(Box::new(msg), trader))
+                    .await;
+            }
+            Ok(None) => {
+                self.try_complete_request(
+                    client_msg_id,
+                    Err(format!("Unsupported response payload type: {}", payload_type)),
+                )
+                .await;
             }
-            Ok(None) => {}
             Err(e) => {
-                let msg = format!("Failed to parse proto: {}", e);
+                self.try_complete_request(client_msg_id, Err(e.clone())).await;
+                self.metrics.on_decode_failure();
+                let msg = redact(&format!("Failed to parse proto: {}", e));
                 self.logger.write_info(PROCESS.into(), msg, None);
             }
         }
     }
 }
+
+/// Risk actions go first, bulk reads of history and lists go last.
+fn get_request_priority(payload_type: ProtoCsPayloadType) -> RequestPriority {
+    match payload_type {
+        ProtoCsPayloadType::ProtoManagerClosePositionReq
+        | ProtoCsPayloadType::ProtoDealerClosePositionReq
+        | ProtoCsPayloadType::ProtoForceClosePositionReq
+        | ProtoCsPayloadType::ProtoForceOpenPositionReq
+        | ProtoCsPayloadType::ProtoManagerCancelOrderReq
+        | ProtoCsPayloadType::ProtoDealerCancelOrderReq
+        | ProtoCsPayloadType::ProtoCrudTraderReq => RequestPriority::High,
+        ProtoCsPayloadType::ProtoBalanceHistoryListReq
+        | ProtoCsPayloadType::ProtoBonusHistoryListReq
+        | ProtoCsPayloadType::ProtoManagerClosedPositionListReq
+        | ProtoCsPayloadType::ProtoManagerDealListReq
+        | ProtoCsPayloadType::ProtoManagerDealListByPositionIdReq
+        | ProtoCsPayloadType::ProtoManualDealListReq
+        | ProtoCsPayloadType::ProtoTraderListReq
+        | ProtoCsPayloadType::ProtoManagerLightTraderListReq
+        | ProtoCsPayloadType::ProtoOrderManagerListReq => RequestPriority::Low,
+        _ => RequestPriority::Normal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::callback::{HandlerCall, HandlerQueue};
+    use crate::manager::event_bus::{EventTrader, OverflowPolicy};
+    use crate::manager::models::{ManagerApiEvent, ManagerApiMessage};
+
+    fn create_message() -> HandlerCall {
+        HandlerCall::Message(
+            Box::new(ManagerApiMessage::Event(ManagerApiEvent::HelloEvent)),
+            EventTrader::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn keeps_connection_calls_when_handler_queue_is_full() {
+        let queue = HandlerQueue::new(2);
+
+        for _ in 0..3 {
+            queue
+                .push(create_message(), OverflowPolicy::DropOldest)
+                .await;
+        }
+
+        assert_eq!(
+            queue
+                .push(HandlerCall::Disconnected, OverflowPolicy::DropOldest)
+                .await,
+            0
+        );
+        assert_eq!(
+            queue
+                .push(create_message(), OverflowPolicy::DropOldest)
+                .await,
+            1
+        );
+        assert_eq!(
+            queue
+                .push(create_message(), OverflowPolicy::Disconnect)
+                .await,
+            3
+        );
+
+        queue.close();
+        assert!(matches!(
+            queue.recv().await,
+            Some(HandlerCall::Disconnected)
+        ));
+        assert!(queue.recv().await.is_none());
+    }
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Duration, Utc};
+
+/// Offset of the cServer clock from the host clock measured with `ProtoServerTimeReq`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerClock {
+    /// Server time minus host time in milliseconds.
+    pub offset_millis: i64,
+    /// Round-trip time of the measuring request in milliseconds.
+    pub round_trip_millis: i64,
+    /// Host time of the measurement.
+    pub measured_at: DateTime<Utc>,
+}
+
+impl ServerClock {
+    /// Calculates the offset assuming the server read its clock in the middle of the round trip.
+    pub fn from_sample(
+        sent_at: DateTime<Utc>,
+        received_at: DateTime<Utc>,
+        server_time_millis: i64,
+    ) -> Self {
+        let round_trip_millis = (received_at - sent_at).num_milliseconds().max(0);
+        let midpoint_millis = sent_at.timestamp_millis() + round_trip_millis / 2;
+
+        Self {
+            offset_millis: server_time_millis - midpoint_millis,
+            round_trip_millis,
+            measured_at: received_at,
+        }
+    }
+
+    /// Converts the specified host time to the server time.
+    pub fn to_server_time(&self, host_time: DateTime<Utc>) -> DateTime<Utc> {
+        host_time + Duration::milliseconds(self.offset_millis)
+    }
+
+    pub fn server_now(&self) -> DateTime<Utc> {
+        self.to_server_time(Utc::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::clock::ServerClock;
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn calculates_offset_from_round_trip_midpoint() {
+        let sent_at = Utc.timestamp_millis_opt(1_000_000).unwrap();
+        let received_at = sent_at + Duration::milliseconds(200);
+
+        let clock = ServerClock::from_sample(sent_at, received_at, 1_005_100);
+
+        assert_eq!(clock.round_trip_millis, 200);
+        assert_eq!(clock.offset_millis, 5_000);
+        assert_eq!(
+            clock.to_server_time(received_at).timestamp_millis(),
+            1_005_200
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use crate::manager::callback::ManagerApiCallbackHandler;
+use crate::manager::cs_messages_external::ProtoExecutionEvent;
+use crate::manager::models::{ManagerApiEvent, ManagerApiMessage};
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+impl ManagerApiEvent {
+    /// Returns ID of the trader (account) the event belongs to, taken from the position, order,
+    /// deal, balance or bonus operation of an execution event or from the trader and session events.
+    /// The ID is used instead of login because login is not sent in positions and deals.
+    pub fn get_trader_id(&self) -> Option<i64> {
+        match self {
+            ManagerApiEvent::ExecutionEvent(event) => event
+                .position
+                .as_ref()
+                .and_then(|position| position.trade_data.trader_id)
+                .or_else(|| {
+                    event
+                        .order
+                        .as_ref()
+                        .and_then(|order| order.trade_data.trader_id)
+                })
+                .or_else(|| event.deal.as_ref().map(|deal| deal.trader_id))
+                .or_else(|| {
+                    event
+                        .deposit_withdraw
+                        .as_ref()
+                        .map(|deposit_withdraw| deposit_withdraw.trader_id)
+                })
+                .or_else(|| {
+                    event
+                        .bonus_deposit_withdraw
+                        .as_ref()
+                        .map(|deposit_withdraw| deposit_withdraw.trader_id)
+                }),
+            ManagerApiEvent::TraderChangedEvent(event) => Some(event.trader.trader_id),
+            ManagerApiEvent::TraderLogonEvent(event) => {
+                Some(event.trade_session_info.trader_id as i64)
+            }
+            ManagerApiEvent::TraderLogoutEvent(event) => event.trader_id.map(|id| id as i64),
+            _ => None,
+        }
+    }
+}
+
+enum DispatchItem {
+    Message(ManagerApiMessage),
+    BigVolumeExecution(ProtoExecutionEvent),
+    /// Confirms that the worker processed all items queued before it.
+    Barrier(oneshot::Sender<()>),
+}
+
+/// Handler that dispatches messages to the inner handler on a pool of workers.
+/// Events of one trader are always processed by the same worker, so they stay in order,
+/// while events of different traders are processed in parallel.
+/// Messages that do not belong to a trader (responses, settings events etc.) are processed
+/// by the first worker. Connection callbacks are passed to the inner handler after every
+/// worker has processed the messages received before them.
+/// Must be created within a tokio runtime.
+pub struct ShardedDispatchHandler<T: ManagerApiCallbackHandler + Send + Sync + 'static> {
+    handler: Arc<T>,
+    workers: Vec<mpsc::Sender<DispatchItem>>,
+}
+
+impl<T: ManagerApiCallbackHandler + Send + Sync + 'static> ShardedDispatchHandler<T> {
+    /// `buffer_size` is the count of messages each worker can queue before the socket
+    /// reading waits for it.
+    pub fn new(handler: Arc<T>, workers_count: usize, buffer_size: usize) -> Self {
+        let workers = (0..workers_count.max(1))
+            .map(|_| {
+                let (sender, mut receiver) = mpsc::channel(buffer_size.max(1));
+                let handler = Arc::clone(&handler);
+
+                tokio::spawn(async move {
+                    while let Some(item) = receiver.recv().await {
+                        match item {
+                            DispatchItem::Message(message) => handler.on_message(message).await,
+                            DispatchItem::BigVolumeExecution(event) => {
+                                handler.on_big_volume_execution(&event).await
+                            }
+                            DispatchItem::Barrier(sender) => {
+                                let _ = sender.send(());
+                            }
+                        }
+                    }
+                });
+
+                sender
+            })
+            .collect();
+
+        Self { handler, workers }
+    }
+
+    pub fn get_workers_count(&self) -> usize {
+        self.workers.len()
+    }
+
+    fn get_worker_index(&self, trader_id: Option<i64>) -> usize {
+        trader_id
+            .map(|id| id.unsigned_abs() as usize % self.workers.len())
+            .unwrap_or(0)
+    }
+
+    async fn dispatch(&self, trader_id: Option<i64>, item: DispatchItem) {
+        let worker = &self.workers[self.get_worker_index(trader_id)];
+        // fails only if the worker task panicked
+        let _ = worker.send(item).await;
+    }
+
+    /// Waits until every worker has processed the items queued before.
+    async fn wait_workers(&self) {
+        let mut receivers = Vec::with_capacity(self.workers.len());
+
+        for worker in &self.workers {
+            let (sender, receiver) = oneshot::channel();
+
+            if worker.send(DispatchItem::Barrier(sender)).await.is_ok() {
+                receivers.push(receiver);
+            }
+        }
+
+        for receiver in receivers {
+            let _ = receiver.await;
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: ManagerApiCallbackHandler + Send + Sync + 'static> ManagerApiCallbackHandler
+    for ShardedDispatchHandler<T>
+{
+    async fn on_connected(&self) {
+        self.wait_workers().await;
+        self.handler.on_connected().await;
+    }
+
+    async fn on_disconnected(&self) {
+        self.wait_workers().await;
+        self.handler.on_disconnected().await;
+    }
+
+    async fn on_message(&self, message: ManagerApiMessage) {
+        let trader_id = match &message {
+            ManagerApiMessage::Event(event) => event.get_trader_id(),
+            _ => None,
+        };
+
+        self.dispatch(trader_id, DispatchItem::Message(message))
+            .await;
+    }
+
+    async fn on_big_volume_execution(&self, event: &ProtoExecutionEvent) {
+        let trader_id = ManagerApiEvent::ExecutionEvent(event.clone()).get_trader_id();
+
+        self.dispatch(trader_id, DispatchItem::BigVolumeExecution(event.clone()))
+            .await;
+    }
+
+    async fn on_reconnect_gave_up(&self, attempts: u32) {
+        self.wait_workers().await;
+        self.handler.on_reconnect_gave_up(attempts).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::callback::ManagerApiCallbackHandler;
+    use crate::manager::cs_messages_external::ProtoTraderLogoutEvent;
+    use crate::manager::dispatcher::ShardedDispatchHandler;
+    use crate::manager::models::{ManagerApiEvent, ManagerApiMessage};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct RecordingHandler {
+        sessions: Mutex<HashMap<u64, Vec<u64>>>,
+        disconnected_count: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl ManagerApiCallbackHandler for RecordingHandler {
+        async fn on_connected(&self) {}
+
+        async fn on_disconnected(&self) {
+            self.disconnected_count.fetch_add(1, Ordering::SeqCst);
+        }
+
+        async fn on_message(&self, message: ManagerApiMessage) {
+            if let ManagerApiMessage::Event(ManagerApiEvent::TraderLogoutEvent(event)) = message {
+                tokio::time::sleep(Duration::from_millis(event.session_id % 3)).await;
+                self.sessions
+                    .lock()
+                    .unwrap()
+                    .entry(event.trader_id.unwrap())
+                    .or_default()
+                    .push(event.session_id);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_order_of_trader_events() {
+        let handler = Arc::new(RecordingHandler::default());
+        let dispatcher = ShardedDispatchHandler::new(Arc::clone(&handler), 4, 8);
+
+        for session_id in 0..40 {
+            let event = ProtoTraderLogoutEvent {
+                payload_type: None,
+                session_id,
+                trader_id: Some(session_id % 5),
+                is_online: None,
+            };
+            dispatcher
+                .on_message(ManagerApiMessage::Event(
+                    ManagerApiEvent::TraderLogoutEvent(event),
+                ))
+                .await;
+        }
+
+        // waits for all workers to process the queued events
+        dispatcher.on_disconnected().await;
+
+        assert_eq!(handler.disconnected_count.load(Ordering::SeqCst), 1);
+        let sessions = handler.sessions.lock().unwrap();
+        assert_eq!(sessions.len(), 5);
+
+        for (trader_id, session_ids) in sessions.iter() {
+            let expected: Vec<u64> = (0..40).filter(|id| id % 5 == *trader_id).collect();
+            assert_eq!(session_ids, &expected);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use crate::manager::cs_messages_external::{
+    ProtoDynamicLeverage, ProtoDynamicLeverageTier, ProtoGslSchedule, ProtoGslScheduleTier,
+};
+
+const SECONDS_IN_DAY: i32 = 24 * 60 * 60;
+
+/// Unit of `ProtoDynamicLeverageTier.leverage`. The proto only says "Leverage of Tier", so
+/// check the unit against a tier of a known leverage on your server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TierLeverageUnit {
+    /// 1:50 = 50.
+    Leverage,
+    /// 1:50 = 5000, the same as `leverageInCents` of a trader.
+    Cents,
+}
+
+impl TierLeverageUnit {
+    fn get_leverage(&self, tier_leverage: i32) -> f64 {
+        match self {
+            Self::Leverage => tier_leverage as f64,
+            Self::Cents => tier_leverage as f64 / 100.0,
+        }
+    }
+}
+
+impl ProtoDynamicLeverage {
+    /// Returns the tier cServer applies to a position with the specified notional
+    /// (USD volume per side): the first tier whose `volume` is not less than the notional.
+    /// A notional above the last tier falls into the last (most restrictive) tier.
+    pub fn get_tier(&self, notional: i64) -> Option<&ProtoDynamicLeverageTier> {
+        self.tiers
+            .iter()
+            .filter(|tier| notional <= tier.volume)
+            .min_by_key(|tier| tier.volume)
+            .or_else(|| self.tiers.iter().max_by_key(|tier| tier.volume))
+    }
+
+    /// Calculates the margin for the specified notional (USD volume per side).
+    /// Every tier is applied only to the part of the notional that falls into it, so the result
+    /// matches the margin cServer calculates for tiered leverage.
+    pub fn calculate_margin(&self, notional: i64, unit: TierLeverageUnit) -> Option<f64> {
+        let tiers = self.get_sorted_tiers();
+
+        if tiers.is_empty() {
+            return None;
+        }
+
+        let mut margin = 0.0;
+        let mut tier_from = 0;
+
+        for (index, tier) in tiers.iter().enumerate() {
+            if notional <= tier_from {
+                break;
+            }
+
+            let tier_to = if index == tiers.len() - 1 {
+                notional
+            } else {
+                notional.min(tier.volume)
+            };
+
+            if tier.leverage <= 0 {
+                return None;
+            }
+
+            margin += (tier_to - tier_from) as f64 / unit.get_leverage(tier.leverage);
+            tier_from = tier_to;
+        }
+
+        Some(margin)
+    }
+
+    fn get_sorted_tiers(&self) -> Vec<&ProtoDynamicLeverageTier> {
+        let mut tiers: Vec<_> = self.tiers.iter().collect();
+        tiers.sort_by_key(|tier| tier.volume);
+
+        tiers
+    }
+}
+
+impl ProtoGslSchedule {
+    /// Returns the tier that is active at the specified count of seconds from 00:00:00.
+    /// Before the first tier of the day the last tier of the previous day is still active.
+    pub fn get_tier(&self, seconds_from_day_start: i32) -> Option<&ProtoGslScheduleTier> {
+        let seconds = seconds_from_day_start.rem_euclid(SECONDS_IN_DAY);
+
+        self.tiers
+            .iter()
+            .filter(|tier| tier.start_time <= seconds)
+            .max_by_key(|tier| tier.start_time)
+            .or_else(|| self.tiers.iter().max_by_key(|tier| tier.start_time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::cs_messages_external::{
+        ProtoDynamicLeverage, ProtoDynamicLeverageTier, ProtoGslSchedule, ProtoGslScheduleTier,
+    };
+    use crate::manager::dynamic_leverage::TierLeverageUnit;
+
+    fn create_leverage() -> ProtoDynamicLeverage {
+        ProtoDynamicLeverage {
+            leverage_id: 1,
+            name: "test".to_string(),
+            description: None,
+            tiers: vec![
+                ProtoDynamicLeverageTier {
+                    volume: 5_000_000,
+                    leverage: 5_000,
+                },
+                ProtoDynamicLeverageTier {
+                    volume: 1_000_000,
+                    leverage: 10_000,
+                },
+            ],
+            utc_last_update_timestamp: None,
+        }
+    }
+
+    #[test]
+    fn returns_effective_leverage_tier() {
+        let leverage = create_leverage();
+
+        assert_eq!(leverage.get_tier(500_000).unwrap().leverage, 10_000);
+        assert_eq!(leverage.get_tier(1_000_000).unwrap().leverage, 10_000);
+        assert_eq!(leverage.get_tier(1_000_001).unwrap().leverage, 5_000);
+        assert_eq!(leverage.get_tier(50_000_000).unwrap().leverage, 5_000);
+    }
+
+    #[test]
+    fn calculates_tiered_margin() {
+        let leverage = create_leverage();
+
+        let unit = TierLeverageUnit::Cents;
+
+        assert_eq!(leverage.calculate_margin(500_000, unit), Some(5_000.0));
+        assert_eq!(
+            leverage.calculate_margin(2_000_000, unit),
+            Some(10_000.0 + 20_000.0)
+        );
+        assert_eq!(
+            leverage.calculate_margin(6_000_000, unit),
+            Some(10_000.0 + 100_000.0)
+        );
+    }
+
+    #[test]
+    fn calculates_margin_from_decoded_tier() {
+        // volume = 1_000_000 (field 1), leverage = 500 (field 2) as encoded on the wire
+        let bytes = [0x08, 0xC0, 0x84, 0x3D, 0x10, 0xF4, 0x03];
+        let tier: ProtoDynamicLeverageTier = prost::Message::decode(&bytes[..]).unwrap();
+        let leverage = ProtoDynamicLeverage {
+            leverage_id: 1,
+            name: "sample".to_string(),
+            description: None,
+            tiers: vec![tier],
+            utc_last_update_timestamp: None,
+        };
+
+        assert_eq!(tier.volume, 1_000_000);
+        assert_eq!(tier.leverage, 500);
+        assert_eq!(
+            leverage.calculate_margin(1_000_000, TierLeverageUnit::Leverage),
+            Some(2_000.0)
+        );
+        assert_eq!(
+            leverage.calculate_margin(1_000_000, TierLeverageUnit::Cents),
+            Some(200_000.0)
+        );
+    }
+
+    #[test]
+    fn returns_active_gsl_tier() {
+        let schedule = ProtoGslSchedule {
+            schedule_id: 1,
+            name: "test".to_string(),
+            tiers: vec![
+                ProtoGslScheduleTier {
+                    start_time: 3_600,
+                    multiplier: 100,
+                },
+                ProtoGslScheduleTier {
+                    start_time: 79_200,
+                    multiplier: 150,
+                },
+            ],
+        };
+
+        assert_eq!(schedule.get_tier(0).unwrap().multiplier, 150);
+        assert_eq!(schedule.get_tier(3_600).unwrap().multiplier, 100);
+        assert_eq!(schedule.get_tier(80_000).unwrap().multiplier, 150);
+    }
+}
@@ -0,0 +1,113 @@
+use crate::manager::transport::{format_host_port, parse_host_port};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Manager API proxy endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManagerApiEndpoint {
+    /// Address in the `host:port` format.
+    pub url: String,
+    /// TLS server name. When it is not set, the host of the url is used.
+    pub server_name: Option<String>,
+}
+
+impl ManagerApiEndpoint {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            server_name: None,
+        }
+    }
+
+    /// Creates the endpoint from the explicit host (a domain name or an IP address, IPv6
+    /// without brackets) and port.
+    pub fn from_host_port(host: &str, port: u16) -> Self {
+        Self::new(format_host_port(host, port))
+    }
+
+    pub fn with_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_name = Some(server_name.into());
+        self
+    }
+
+    pub fn get_server_name(&self) -> String {
+        if let Some(server_name) = &self.server_name {
+            return server_name.clone();
+        }
+
+        match parse_host_port(&self.url) {
+            Ok((host, _)) => host,
+            Err(_) => self.url.clone(),
+        }
+    }
+}
+
+/// Index of the active endpoint in the ordered list. The first endpoint is the primary one.
+#[derive(Debug, Default)]
+pub struct EndpointRotation {
+    active_index: AtomicUsize,
+}
+
+impl EndpointRotation {
+    pub fn get_active_index(&self, endpoints_count: usize) -> usize {
+        let index = self.active_index.load(Ordering::SeqCst);
+
+        if index >= endpoints_count {
+            return 0;
+        }
+
+        index
+    }
+
+    /// Switches to the next endpoint (after the last one goes the primary) and returns its index.
+    pub fn rotate(&self, endpoints_count: usize) -> usize {
+        if endpoints_count == 0 {
+            return 0;
+        }
+
+        let next_index = (self.get_active_index(endpoints_count) + 1) % endpoints_count;
+        self.active_index.store(next_index, Ordering::SeqCst);
+
+        next_index
+    }
+
+    pub fn reset_to_primary(&self) {
+        self.active_index.store(0, Ordering::SeqCst);
+    }
+
+    pub fn is_primary_active(&self, endpoints_count: usize) -> bool {
+        self.get_active_index(endpoints_count) == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::endpoints::{EndpointRotation, ManagerApiEndpoint};
+
+    #[test]
+    fn rotates_endpoints() {
+        let rotation = EndpointRotation::default();
+
+        assert!(rotation.is_primary_active(3));
+        assert_eq!(rotation.rotate(3), 1);
+        assert_eq!(rotation.rotate(3), 2);
+        assert_eq!(rotation.rotate(3), 0);
+        assert_eq!(rotation.rotate(3), 1);
+        // the list became shorter
+        assert_eq!(rotation.get_active_index(1), 0);
+        rotation.reset_to_primary();
+        assert!(rotation.is_primary_active(3));
+    }
+
+    #[test]
+    fn gets_server_name() {
+        let endpoint = ManagerApiEndpoint::new("backup.example.com:5011");
+        assert_eq!(endpoint.get_server_name(), "backup.example.com");
+
+        let endpoint = endpoint.with_server_name("proxy.example.com");
+        assert_eq!(endpoint.get_server_name(), "proxy.example.com");
+
+        let endpoint = ManagerApiEndpoint::from_host_port("2001:db8::1", 5011);
+        assert_eq!(endpoint.url, "[2001:db8::1]:5011");
+        assert_eq!(endpoint.get_server_name(), "2001:db8::1");
+    }
+}
@@ -0,0 +1,481 @@
+use crate::manager::models::{ManagerApiEvent, ManagerApiEventKind, ManagerApiMessage};
+use futures_util::stream::Stream;
+use std::collections::{HashSet, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::{Mutex, Notify};
+
+impl ManagerApiEvent {
+    pub fn get_kind(&self) -> ManagerApiEventKind {
+        ManagerApiEventKind::from(self)
+    }
+
+    /// Returns TRUE for events that concern a single trader: executions (orders, positions,
+    /// deals, deposits and withdrawals), trader changes, logons, logouts and margin changes.
+    pub fn is_trader_event(&self) -> bool {
+        matches!(
+            self,
+            ManagerApiEvent::ExecutionEvent(_)
+                | ManagerApiEvent::TraderChangedEvent(_)
+                | ManagerApiEvent::TraderLogonEvent(_)
+                | ManagerApiEvent::TraderLogoutEvent(_)
+                | ManagerApiEvent::MarginChangedEvent(_)
+        )
+    }
+
+    /// Returns the trader login if the event carries it. Only orders and trader changes carry
+    /// the login, other trader events carry the trader id, see `get_trader_id`.
+    pub fn get_login(&self) -> Option<i64> {
+        match self {
+            ManagerApiEvent::ExecutionEvent(event) => {
+                event.order.as_ref().and_then(|order| order.login)
+            }
+            ManagerApiEvent::TraderChangedEvent(event) => Some(event.trader.login),
+            _ => None,
+        }
+    }
+
+    /// Returns the trader group id if the event carries it. Only trader changes carry
+    /// the group, see `TraderSessionRegistry::resolve_trader` for the other events.
+    pub fn get_group_id(&self) -> Option<i64> {
+        match self {
+            ManagerApiEvent::TraderChangedEvent(event) => Some(event.trader.group_id),
+            _ => None,
+        }
+    }
+}
+
+/// Login and group of the trader an event concerns. `None` if it is not known.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventTrader {
+    pub login: Option<i64>,
+    pub group_id: Option<i64>,
+}
+
+impl EventTrader {
+    /// Takes the login and the group the event carries itself.
+    pub fn from_event(event: &ManagerApiEvent) -> Self {
+        Self {
+            login: event.get_login(),
+            group_id: event.get_group_id(),
+        }
+    }
+
+    pub fn from_message(message: &ManagerApiMessage) -> Self {
+        match message {
+            ManagerApiMessage::Event(event) => Self::from_event(event),
+            _ => Self::default(),
+        }
+    }
+}
+
+/// Filter of the messages delivered to a subscriber. An empty filter matches all messages.
+/// Responses and errors are delivered only to subscribers with an empty filter.
+///
+/// The login and group filters skip events that do not concern a trader (see
+/// `ManagerApiEvent::is_trader_event`). A trader event whose login or group can not be
+/// resolved is delivered, not skipped: `MarginChangedEvent` carries only the position id,
+/// and the login and group of other events are known only for traders of the login index
+/// (see `TraderSessionRegistry`). Subscribers must check such events themselves or exclude
+/// them with `event_kinds`.
+#[derive(Debug, Clone, Default)]
+pub struct SubscriptionFilter {
+    pub event_kinds: Option<HashSet<ManagerApiEventKind>>,
+    pub logins: Option<HashSet<i64>>,
+    pub group_ids: Option<HashSet<i64>>,
+}
+
+impl SubscriptionFilter {
+    pub fn with_event_kinds(
+        mut self,
+        kinds: impl IntoIterator<Item = ManagerApiEventKind>,
+    ) -> Self {
+        self.event_kinds = Some(kinds.into_iter().collect());
+        self
+    }
+
+    pub fn with_logins(mut self, logins: impl IntoIterator<Item = i64>) -> Self {
+        self.logins = Some(logins.into_iter().collect());
+        self
+    }
+
+    pub fn with_group_ids(mut self, group_ids: impl IntoIterator<Item = i64>) -> Self {
+        self.group_ids = Some(group_ids.into_iter().collect());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.event_kinds.is_none() && self.logins.is_none() && self.group_ids.is_none()
+    }
+
+    /// Matches the message by the login and the group the event carries itself.
+    pub fn matches(&self, message: &ManagerApiMessage) -> bool {
+        let trader = EventTrader::from_message(message);
+
+        self.matches_trader(message, &trader)
+    }
+
+    /// Matches the message by the resolved login and group of the trader.
+    pub fn matches_trader(&self, message: &ManagerApiMessage, trader: &EventTrader) -> bool {
+        let ManagerApiMessage::Event(event) = message else {
+            return self.is_empty();
+        };
+
+        if let Some(kinds) = &self.event_kinds {
+            if !kinds.contains(&event.get_kind()) {
+                return false;
+            }
+        }
+
+        if self.logins.is_none() && self.group_ids.is_none() {
+            return true;
+        }
+
+        if !event.is_trader_event() {
+            return false;
+        }
+
+        if let (Some(logins), Some(login)) = (&self.logins, trader.login) {
+            if !logins.contains(&login) {
+                return false;
+            }
+        }
+
+        if let (Some(group_ids), Some(group_id)) = (&self.group_ids, trader.group_id) {
+            if !group_ids.contains(&group_id) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// What to do when the buffer of a subscriber is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Waits until the subscriber reads a message. Stalls the delivery of messages to the
+    /// handler and the other subscribers, but not reading of the socket.
+    Block,
+    /// Drops the oldest buffered message.
+    DropOldest,
+    /// Closes the subscription; the subscriber receives the buffered messages and then `None`.
+    Disconnect,
+}
+
+struct SubscriberQueue {
+    messages: std::sync::Mutex<VecDeque<ManagerApiMessage>>,
+    capacity: usize,
+    is_closed: AtomicBool,
+    dropped_count: AtomicU64,
+    message_notify: Notify,
+    space_notify: Notify,
+}
+
+impl SubscriberQueue {
+    fn close(&self) {
+        self.is_closed.store(true, Ordering::SeqCst);
+        self.message_notify.notify_one();
+        self.space_notify.notify_one();
+    }
+
+    fn is_closed(&self) -> bool {
+        self.is_closed.load(Ordering::SeqCst)
+    }
+
+    async fn recv(&self) -> Option<ManagerApiMessage> {
+        loop {
+            let message = self.messages.lock().unwrap().pop_front();
+
+            if let Some(message) = message {
+                self.space_notify.notify_one();
+                return Some(message);
+            }
+
+            if self.is_closed() {
+                return None;
+            }
+
+            self.message_notify.notified().await;
+        }
+    }
+
+    /// Returns FALSE if the subscriber must be removed.
+    async fn push(&self, message: ManagerApiMessage, policy: OverflowPolicy) -> bool {
+        loop {
+            if self.is_closed() {
+                return false;
+            }
+
+            {
+                let mut messages = self.messages.lock().unwrap();
+
+                if messages.len() < self.capacity {
+                    messages.push_back(message);
+                    self.message_notify.notify_one();
+                    return true;
+                }
+
+                match policy {
+                    OverflowPolicy::Block => {}
+                    OverflowPolicy::DropOldest => {
+                        messages.pop_front();
+                        messages.push_back(message);
+                        self.dropped_count.fetch_add(1, Ordering::SeqCst);
+                        self.message_notify.notify_one();
+                        return true;
+                    }
+                    OverflowPolicy::Disconnect => {
+                        drop(messages);
+                        self.close();
+                        return false;
+                    }
+                }
+            }
+
+            self.space_notify.notified().await;
+        }
+    }
+}
+
+struct Subscriber {
+    filter: SubscriptionFilter,
+    policy: OverflowPolicy,
+    queue: Arc<SubscriberQueue>,
+}
+
+/// Delivers messages to independent subscribers, each with its own bounded buffer.
+#[derive(Clone, Default)]
+pub struct ManagerApiEventBus {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl ManagerApiEventBus {
+    /// `capacity` is the size of the subscriber buffer, must be greater than 0.
+    pub async fn subscribe(
+        &self,
+        filter: SubscriptionFilter,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> Subscription {
+        let queue = Arc::new(SubscriberQueue {
+            messages: std::sync::Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity: capacity.max(1),
+            is_closed: AtomicBool::new(false),
+            dropped_count: AtomicU64::new(0),
+            message_notify: Notify::new(),
+            space_notify: Notify::new(),
+        });
+        self.subscribers.lock().await.push(Subscriber {
+            filter,
+            policy,
+            queue: Arc::clone(&queue),
+        });
+
+        Subscription {
+            queue,
+            pending: None,
+        }
+    }
+
+    pub async fn get_subscribers_count(&self) -> usize {
+        self.subscribers.lock().await.len()
+    }
+
+    /// Pushes the message to the matching subscribers. The subscribers list is not locked
+    /// while waiting for a subscriber with `OverflowPolicy::Block`, so subscribing and
+    /// unsubscribing are not blocked by a slow subscriber.
+    pub async fn publish(&self, message: &ManagerApiMessage) {
+        let trader = EventTrader::from_message(message);
+
+        self.publish_for_trader(message, &trader).await;
+    }
+
+    /// Same as `publish`, but filters by the resolved login and group of the trader.
+    pub async fn publish_for_trader(&self, message: &ManagerApiMessage, trader: &EventTrader) {
+        let subscribers: Vec<(Arc<SubscriberQueue>, OverflowPolicy)> = self
+            .subscribers
+            .lock()
+            .await
+            .iter()
+            .filter(|subscriber| subscriber.filter.matches_trader(message, trader))
+            .map(|subscriber| (Arc::clone(&subscriber.queue), subscriber.policy))
+            .collect();
+
+        for (queue, policy) in subscribers {
+            queue.push(message.clone(), policy).await;
+        }
+
+        self.subscribers
+            .lock()
+            .await
+            .retain(|subscriber| !subscriber.queue.is_closed());
+    }
+
+    /// Closes all subscriptions, subscribers receive the buffered messages and then `None`.
+    pub async fn close(&self) {
+        for subscriber in self.subscribers.lock().await.drain(..) {
+            subscriber.queue.close();
+        }
+    }
+}
+
+type RecvFuture = Pin<Box<dyn Future<Output = Option<ManagerApiMessage>> + Send>>;
+
+/// Stream of the messages matching the subscription filter. Dropping it unsubscribes.
+pub struct Subscription {
+    queue: Arc<SubscriberQueue>,
+    pending: Option<RecvFuture>,
+}
+
+impl Subscription {
+    pub async fn recv(&mut self) -> Option<ManagerApiMessage> {
+        self.queue.recv().await
+    }
+
+    /// Count of messages dropped by `OverflowPolicy::DropOldest`.
+    pub fn get_dropped_count(&self) -> u64 {
+        self.queue.dropped_count.load(Ordering::SeqCst)
+    }
+
+    /// Returns TRUE if the subscription was closed by `OverflowPolicy::Disconnect` or by the bus.
+    pub fn is_closed(&self) -> bool {
+        self.queue.is_closed()
+    }
+}
+
+impl Stream for Subscription {
+    type Item = ManagerApiMessage;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let queue = Arc::clone(&this.queue);
+        let pending = this
+            .pending
+            .get_or_insert_with(|| Box::pin(async move { queue.recv().await }));
+        let result = pending.as_mut().poll(cx);
+
+        if result.is_ready() {
+            this.pending = None;
+        }
+
+        result
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::cs_messages_external::{ProtoMarginChangedEvent, ProtoTraderLogoutEvent};
+    use crate::manager::event_bus::{
+        EventTrader, ManagerApiEventBus, OverflowPolicy, SubscriptionFilter,
+    };
+    use crate::manager::models::{ManagerApiEvent, ManagerApiEventKind, ManagerApiMessage};
+
+    #[tokio::test]
+    async fn delivers_filtered_messages_with_overflow_policies() {
+        let bus = ManagerApiEventBus::default();
+        let hello_filter =
+            SubscriptionFilter::default().with_event_kinds([ManagerApiEventKind::HelloEvent]);
+        let mut drop_oldest = bus
+            .subscribe(hello_filter, 2, OverflowPolicy::DropOldest)
+            .await;
+        let mut disconnect = bus
+            .subscribe(SubscriptionFilter::default(), 1, OverflowPolicy::Disconnect)
+            .await;
+        let mut logins = bus
+            .subscribe(
+                SubscriptionFilter::default().with_logins([1]),
+                1,
+                OverflowPolicy::Block,
+            )
+            .await;
+
+        for _ in 0..3 {
+            bus.publish(&ManagerApiMessage::Event(ManagerApiEvent::HelloEvent))
+                .await;
+        }
+
+        assert_eq!(drop_oldest.get_dropped_count(), 1);
+        assert!(drop_oldest.recv().await.is_some());
+        assert!(drop_oldest.recv().await.is_some());
+        assert!(disconnect.is_closed());
+        assert!(disconnect.recv().await.is_some());
+        assert!(disconnect.recv().await.is_none());
+        assert_eq!(bus.get_subscribers_count().await, 2);
+
+        drop(drop_oldest);
+        bus.close().await;
+        assert!(logins.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn subscribes_while_blocked_subscriber_is_full() {
+        let bus = ManagerApiEventBus::default();
+        let mut blocked = bus
+            .subscribe(SubscriptionFilter::default(), 1, OverflowPolicy::Block)
+            .await;
+        bus.publish(&ManagerApiMessage::Event(ManagerApiEvent::HelloEvent))
+            .await;
+
+        let publishing_bus = bus.clone();
+        let publishing = tokio::spawn(async move {
+            publishing_bus
+                .publish(&ManagerApiMessage::Event(ManagerApiEvent::HelloEvent))
+                .await;
+        });
+        tokio::task::yield_now().await;
+
+        let subscription = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            bus.subscribe(SubscriptionFilter::default(), 1, OverflowPolicy::DropOldest),
+        )
+        .await;
+        assert!(subscription.is_ok());
+
+        assert!(blocked.recv().await.is_some());
+        publishing.await.unwrap();
+        assert!(blocked.recv().await.is_some());
+    }
+
+    #[test]
+    fn filters_by_resolved_trader_and_delivers_unresolved_trader_events() {
+        let filter = SubscriptionFilter::default()
+            .with_logins([1010])
+            .with_group_ids([5]);
+        let logout =
+            ManagerApiMessage::Event(ManagerApiEvent::TraderLogoutEvent(ProtoTraderLogoutEvent {
+                payload_type: None,
+                session_id: 1,
+                trader_id: Some(10),
+                is_online: None,
+            }));
+        let margin_changed = ManagerApiMessage::Event(ManagerApiEvent::MarginChangedEvent(
+            ProtoMarginChangedEvent {
+                payload_type: None,
+                position_id: 1,
+                used_margin: 100,
+                money_digits: None,
+            },
+        ));
+        let resolved = |login, group_id| EventTrader {
+            login: Some(login),
+            group_id: Some(group_id),
+        };
+
+        assert!(filter.matches_trader(&logout, &resolved(1010, 5)));
+        assert!(!filter.matches_trader(&logout, &resolved(1020, 5)));
+        assert!(!filter.matches_trader(&logout, &resolved(1010, 6)));
+        assert!(filter.matches(&logout));
+        assert!(filter.matches(&margin_changed));
+        assert!(!filter.matches(&ManagerApiMessage::Event(ManagerApiEvent::HelloEvent)));
+    }
+}
@@ -0,0 +1,172 @@
+use crate::manager::cs_messages_external::{ProtoBookType, ProtoExposureSymbolRecord};
+use std::collections::HashMap;
+
+/// Exposure aggregated over a set of symbol records. Volumes are in USD.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExposureSummary {
+    pub position_count: i64,
+    pub buy_volume: i64,
+    pub sell_volume: i64,
+}
+
+impl ExposureSummary {
+    /// Buy volume minus sell volume in USD.
+    pub fn get_net_volume(&self) -> i64 {
+        self.buy_volume - self.sell_volume
+    }
+
+    fn add(&mut self, record: &ProtoExposureSymbolRecord) {
+        self.position_count += record.position_count as i64;
+        self.buy_volume += record.buy_volume;
+        self.sell_volume += record.sell_volume;
+    }
+}
+
+/// Change of the exposure record of a symbol in a book between two polls.
+/// `previous` is `None` for a new record and `current` is `None` for a removed one.
+#[derive(Debug, Clone)]
+pub struct ExposureChange {
+    pub symbol_id: i64,
+    pub book_type: i32,
+    pub previous: Option<ProtoExposureSymbolRecord>,
+    pub current: Option<ProtoExposureSymbolRecord>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExposureReport {
+    pub records: Vec<ProtoExposureSymbolRecord>,
+}
+
+impl ExposureReport {
+    pub fn new(records: Vec<ProtoExposureSymbolRecord>) -> Self {
+        Self { records }
+    }
+
+    pub fn by_symbol(&self) -> HashMap<i64, ExposureSummary> {
+        self.aggregate(|record| Some(record.symbol_id))
+    }
+
+    pub fn by_book_type(&self) -> HashMap<ProtoBookType, ExposureSummary> {
+        self.aggregate(|record| ProtoBookType::try_from(record.book_type).ok())
+    }
+
+    /// Aggregates exposure by asset class using the specified symbol id to asset class id map.
+    /// Records of symbols that are missing in the map are aggregated under `None`.
+    pub fn by_asset_class(
+        &self,
+        symbol_asset_classes: &HashMap<i64, i64>,
+    ) -> HashMap<Option<i64>, ExposureSummary> {
+        self.aggregate(|record| Some(symbol_asset_classes.get(&record.symbol_id).copied()))
+    }
+
+    /// Returns changed, added and removed records compared to the previous report.
+    pub fn get_changes(&self, previous: &ExposureReport) -> Vec<ExposureChange> {
+        let mut previous_records: HashMap<(i64, i32), &ProtoExposureSymbolRecord> = previous
+            .records
+            .iter()
+            .map(|record| ((record.symbol_id, record.book_type), record))
+            .collect();
+        let mut changes = Vec::new();
+
+        for record in self.records.iter() {
+            let previous_record = previous_records.remove(&(record.symbol_id, record.book_type));
+
+            if previous_record == Some(record) {
+                continue;
+            }
+
+            changes.push(ExposureChange {
+                symbol_id: record.symbol_id,
+                book_type: record.book_type,
+                previous: previous_record.copied(),
+                current: Some(*record),
+            });
+        }
+
+        for ((symbol_id, book_type), record) in previous_records {
+            changes.push(ExposureChange {
+                symbol_id,
+                book_type,
+                previous: Some(*record),
+                current: None,
+            });
+        }
+
+        changes
+    }
+
+    fn aggregate<K: std::hash::Hash + Eq>(
+        &self,
+        get_key: impl Fn(&ProtoExposureSymbolRecord) -> Option<K>,
+    ) -> HashMap<K, ExposureSummary> {
+        let mut result: HashMap<K, ExposureSummary> = HashMap::new();
+
+        for record in self.records.iter() {
+            if let Some(key) = get_key(record) {
+                result.entry(key).or_default().add(record);
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::cs_messages_external::{ProtoBookType, ProtoExposureSymbolRecord};
+    use crate::manager::exposure::ExposureReport;
+    use std::collections::HashMap;
+
+    fn create_record(
+        symbol_id: i64,
+        book_type: ProtoBookType,
+        buy_volume: i64,
+    ) -> ProtoExposureSymbolRecord {
+        ProtoExposureSymbolRecord {
+            symbol_id,
+            book_type: book_type as i32,
+            position_count: 1,
+            buy_volume,
+            sell_volume: 100,
+            buy_vwap: 1.0,
+            sell_vwap: 1.0,
+        }
+    }
+
+    #[test]
+    fn aggregates_exposure() {
+        let report = ExposureReport::new(vec![
+            create_record(1, ProtoBookType::BookA, 1_000),
+            create_record(1, ProtoBookType::BookB, 500),
+            create_record(2, ProtoBookType::BookB, 300),
+        ]);
+
+        let by_book_type = report.by_book_type();
+        assert_eq!(by_book_type[&ProtoBookType::BookA].get_net_volume(), 900);
+        assert_eq!(by_book_type[&ProtoBookType::BookB].get_net_volume(), 600);
+        assert_eq!(by_book_type[&ProtoBookType::BookB].position_count, 2);
+
+        let by_asset_class = report.by_asset_class(&HashMap::from([(1, 10)]));
+        assert_eq!(by_asset_class[&Some(10)].buy_volume, 1_500);
+        assert_eq!(by_asset_class[&None].buy_volume, 300);
+    }
+
+    #[test]
+    fn detects_exposure_changes() {
+        let previous = ExposureReport::new(vec![
+            create_record(1, ProtoBookType::BookA, 1_000),
+            create_record(2, ProtoBookType::BookA, 1_000),
+        ]);
+        let current = ExposureReport::new(vec![
+            create_record(1, ProtoBookType::BookA, 1_000),
+            create_record(3, ProtoBookType::BookA, 1_000),
+        ]);
+
+        let mut changes = current.get_changes(&previous);
+        changes.sort_by_key(|change| change.symbol_id);
+
+        assert_eq!(changes.len(), 2);
+        assert!(changes[0].current.is_none());
+        assert!(changes[1].previous.is_none());
+    }
+}
@@ -0,0 +1,91 @@
+use crate::manager::cs_messages_external::{ProtoLiquidityFeed, ProtoLiquidityFeedStatusRes};
+
+impl ProtoLiquidityFeedStatusRes {
+    /// Returns TRUE if the pricing or trading connection is used by the feed but not logged on.
+    pub fn is_down(&self) -> bool {
+        (self.pricing_enabled && !self.pricing_logged_on)
+            || (self.trading_enabled && !self.trading_logged_on)
+    }
+}
+
+/// Change of the connection state of a Liquidity Feed between two polls.
+#[derive(Debug, Clone)]
+pub struct LiquidityFeedStatusChange {
+    pub feed: ProtoLiquidityFeed,
+    /// `None` when the feed is observed for the first time.
+    pub previous: Option<ProtoLiquidityFeedStatusRes>,
+    pub current: ProtoLiquidityFeedStatusRes,
+}
+
+impl LiquidityFeedStatusChange {
+    pub fn went_down(&self) -> bool {
+        self.current.is_down() && !self.previous.is_some_and(|status| status.is_down())
+    }
+
+    pub fn recovered(&self) -> bool {
+        !self.current.is_down() && self.previous.is_some_and(|status| status.is_down())
+    }
+}
+
+/// Returns the change if the connection state differs from the previous observation.
+pub fn get_status_change(
+    feed: &ProtoLiquidityFeed,
+    previous: Option<&ProtoLiquidityFeedStatusRes>,
+    current: &ProtoLiquidityFeedStatusRes,
+) -> Option<LiquidityFeedStatusChange> {
+    let is_changed = previous
+        .map(|previous| {
+            previous.pricing_logged_on != current.pricing_logged_on
+                || previous.trading_logged_on != current.trading_logged_on
+                || previous.is_down() != current.is_down()
+        })
+        .unwrap_or(true);
+
+    if !is_changed {
+        return None;
+    }
+
+    Some(LiquidityFeedStatusChange {
+        feed: feed.clone(),
+        previous: previous.copied(),
+        current: *current,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::cs_messages_external::{ProtoLiquidityFeed, ProtoLiquidityFeedStatusRes};
+    use crate::manager::liquidity::get_status_change;
+
+    fn create_status(pricing_logged_on: bool) -> ProtoLiquidityFeedStatusRes {
+        ProtoLiquidityFeedStatusRes {
+            payload_type: None,
+            pricing_logged_on,
+            pricing_enabled: true,
+            trading_logged_on: true,
+            trading_enabled: true,
+            status_timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn detects_feed_going_down_and_recovering() {
+        let feed = ProtoLiquidityFeed {
+            liquidity_feed_id: 1,
+            r#type: "FIX".to_string(),
+            name: "LP".to_string(),
+            ..Default::default()
+        };
+        let up = create_status(true);
+        let down = create_status(false);
+
+        assert!(get_status_change(&feed, Some(&up), &up).is_none());
+        assert!(get_status_change(&feed, Some(&up), &down)
+            .unwrap()
+            .went_down());
+        assert!(get_status_change(&feed, Some(&down), &up)
+            .unwrap()
+            .recovered());
+        assert!(!get_status_change(&feed, None, &up).unwrap().went_down());
+    }
+}
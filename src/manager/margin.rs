@@ -0,0 +1,31 @@
+use crate::manager::cs_messages_external::ProtoMarginChangedEvent;
+
+impl ProtoMarginChangedEvent {
+    /// Returns the used margin of the position with `moneyDigits` applied.
+    /// The value is not scaled when `moneyDigits` is not specified.
+    pub fn get_used_margin(&self) -> f64 {
+        let money_digits = self.money_digits.unwrap_or(0);
+
+        self.used_margin as f64 / 10f64.powi(money_digits as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::cs_messages_external::ProtoMarginChangedEvent;
+
+    #[test]
+    fn applies_money_digits_to_used_margin() {
+        let mut event = ProtoMarginChangedEvent {
+            payload_type: None,
+            position_id: 1,
+            used_margin: 10_053_099_944,
+            money_digits: Some(8),
+        };
+
+        assert_eq!(event.get_used_margin(), 100.53099944);
+
+        event.money_digits = None;
+        assert_eq!(event.get_used_margin(), 10_053_099_944.0);
+    }
+}
@@ -1,7 +1,23 @@
 pub mod api_client;
 pub mod callback;
+pub mod clock;
+pub mod dispatcher;
+pub mod dynamic_leverage;
+pub mod endpoints;
+pub mod event_bus;
+pub mod exposure;
+pub mod liquidity;
+pub mod margin;
 pub mod models;
+pub mod options;
+pub mod recording;
+pub mod reference_data;
+mod probe;
+pub(crate) mod relay;
 pub mod serialization;
+pub mod sessions;
+pub mod shutdown;
+pub mod transport;
 
 pub mod common_messages_external {
     tonic::include_proto!("common_messages_external");
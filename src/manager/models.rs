@@ -1,8 +1,26 @@
 use crate::manager::common_messages_external::{ProtoErrorRes, ProtoMessage};
 use crate::manager::cs_messages_external::{
-    ProtoBalanceHistoryListRes, ProtoCsPayloadType, ProtoExecutionEvent, ProtoManagerAuthRes,
-    ProtoOrderDetailsRes, ProtoOrderErrorEvent, ProtoTraderChangedEvent, ProtoTraderListRes,
-    ProtoTraderLogonEvent, ProtoTraderLogoutEvent,
+    ProtoAssetChangedEvent, ProtoAssetClassChangedEvent, ProtoAssetClassDeletedEvent,
+    ProtoAssetClassListRes, ProtoAssetListRes, ProtoBalanceHistoryListRes, ProtoCountryListRes,
+    ProtoCreatePriceStreamRes, ProtoCrudDynamicLeverageRes, ProtoCrudGslScheduleRes,
+    ProtoCrudLiquidityFeedSymbolRes, ProtoCrudManagerRes, ProtoCrudMaxAutoExecutionSizeProfileRes,
+    ProtoCrudTradeNotificationProfileRes, ProtoCsPayloadType, ProtoDealingSettingsRes,
+    ProtoDealingSettingsUpdatedEvent, ProtoDeletePriceStreamRes, ProtoDynamicLeverageChangedEvent,
+    ProtoDynamicLeverageListRes, ProtoExecutionEvent, ProtoExposureSymbolListRes,
+    ProtoGetSymbolsForConversionRes, ProtoGslScheduleChangedEvent, ProtoGslScheduleListRes,
+    ProtoLiquidityFeedListRes, ProtoLiquidityFeedStatusRes, ProtoLiquidityFeedSymbolChangedEvent,
+    ProtoLiquidityFeedSymbolListRes, ProtoManagerAuthRes, ProtoManagerByIdRes,
+    ProtoManagerChangedEvent, ProtoManagerGetAuthTokenRes, ProtoManagerListRes,
+    ProtoMarginChangedEvent, ProtoMaxAutoExecutionSizeProfileChangedEvent,
+    ProtoMaxAutoExecutionSizeProfileListRes, ProtoOrderDetailsRes, ProtoOrderErrorEvent,
+    ProtoPriceStreamChangedEvent, ProtoPriceStreamListRes, ProtoRecalculateAccountMarginRes,
+    ProtoRecalculateDynamicLeverageMarginRes, ProtoRecalculateSymbolMarginRes,
+    ProtoServerSettingsChangedEvent, ProtoServerSettingsRes, ProtoServerTimeRes,
+    ProtoSymbolCategoryChangedEvent, ProtoSymbolCategoryDeletedEvent, ProtoSymbolCategoryListRes,
+    ProtoTradeNotificationProfileChangedEvent, ProtoTradeNotificationProfileListRes,
+    ProtoTraderChangedEvent, ProtoTraderListRes, ProtoTraderLogonEvent, ProtoTraderLogoutEvent,
+    ProtoUpdateDealingSettingsRes, ProtoUpdatePriceStreamRes, ProtoUpdateServerSettingsRes,
+    ProtoVersionRes,
 };
 
 #[derive(Debug, Clone)]
@@ -23,15 +41,66 @@ pub enum ManagerApiResponse {
     TraderListRes(ProtoTraderListRes),
     OrderDetailsRes(ProtoOrderDetailsRes),
     BalanceHistoryRes(ProtoBalanceHistoryListRes),
+    DynamicLeverageListRes(ProtoDynamicLeverageListRes),
+    CrudDynamicLeverageRes(ProtoCrudDynamicLeverageRes),
+    RecalculateDynamicLeverageMarginRes(ProtoRecalculateDynamicLeverageMarginRes),
+    GslScheduleListRes(ProtoGslScheduleListRes),
+    CrudGslScheduleRes(ProtoCrudGslScheduleRes),
+    ExposureSymbolListRes(ProtoExposureSymbolListRes),
+    LiquidityFeedListRes(ProtoLiquidityFeedListRes),
+    LiquidityFeedStatusRes(ProtoLiquidityFeedStatusRes),
+    LiquidityFeedSymbolListRes(ProtoLiquidityFeedSymbolListRes),
+    CrudLiquidityFeedSymbolRes(ProtoCrudLiquidityFeedSymbolRes),
+    PriceStreamListRes(ProtoPriceStreamListRes),
+    CreatePriceStreamRes(ProtoCreatePriceStreamRes),
+    UpdatePriceStreamRes(ProtoUpdatePriceStreamRes),
+    DeletePriceStreamRes(ProtoDeletePriceStreamRes),
+    ServerSettingsRes(ProtoServerSettingsRes),
+    UpdateServerSettingsRes(ProtoUpdateServerSettingsRes),
+    DealingSettingsRes(ProtoDealingSettingsRes),
+    UpdateDealingSettingsRes(ProtoUpdateDealingSettingsRes),
+    ManagerListRes(ProtoManagerListRes),
+    ManagerByIdRes(ProtoManagerByIdRes),
+    CrudManagerRes(ProtoCrudManagerRes),
+    TradeNotificationProfileListRes(ProtoTradeNotificationProfileListRes),
+    CrudTradeNotificationProfileRes(ProtoCrudTradeNotificationProfileRes),
+    MaxAutoExecutionSizeProfileListRes(ProtoMaxAutoExecutionSizeProfileListRes),
+    CrudMaxAutoExecutionSizeProfileRes(ProtoCrudMaxAutoExecutionSizeProfileRes),
+    RecalculateAccountMarginRes(ProtoRecalculateAccountMarginRes),
+    RecalculateSymbolMarginRes(ProtoRecalculateSymbolMarginRes),
+    ServerTimeRes(ProtoServerTimeRes),
+    VersionRes(ProtoVersionRes),
+    CountryListRes(ProtoCountryListRes),
+    AssetListRes(ProtoAssetListRes),
+    AssetClassListRes(ProtoAssetClassListRes),
+    SymbolCategoryListRes(ProtoSymbolCategoryListRes),
+    SymbolsForConversionRes(ProtoGetSymbolsForConversionRes),
+    ManagerGetAuthTokenRes(ProtoManagerGetAuthTokenRes),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, strum::EnumDiscriminants)]
+#[strum_discriminants(name(ManagerApiEventKind), derive(Hash))]
 pub enum ManagerApiEvent {
     HelloEvent,
     ExecutionEvent(ProtoExecutionEvent),
     TraderChangedEvent(ProtoTraderChangedEvent),
     TraderLogonEvent(ProtoTraderLogonEvent),
     TraderLogoutEvent(ProtoTraderLogoutEvent),
+    DynamicLeverageChangedEvent(ProtoDynamicLeverageChangedEvent),
+    GslScheduleChangedEvent(ProtoGslScheduleChangedEvent),
+    PriceStreamChangedEvent(ProtoPriceStreamChangedEvent),
+    LiquidityFeedSymbolChangedEvent(ProtoLiquidityFeedSymbolChangedEvent),
+    ServerSettingsChangedEvent(ProtoServerSettingsChangedEvent),
+    DealingSettingsUpdatedEvent(ProtoDealingSettingsUpdatedEvent),
+    ManagerChangedEvent(ProtoManagerChangedEvent),
+    TradeNotificationProfileChangedEvent(ProtoTradeNotificationProfileChangedEvent),
+    MaxAutoExecutionSizeProfileChangedEvent(ProtoMaxAutoExecutionSizeProfileChangedEvent),
+    MarginChangedEvent(ProtoMarginChangedEvent),
+    AssetChangedEvent(ProtoAssetChangedEvent),
+    AssetClassChangedEvent(ProtoAssetClassChangedEvent),
+    AssetClassDeletedEvent(ProtoAssetClassDeletedEvent),
+    SymbolCategoryChangedEvent(ProtoSymbolCategoryChangedEvent),
+    SymbolCategoryDeletedEvent(ProtoSymbolCategoryDeletedEvent),
 }
 
 impl ManagerApiMessage {
@@ -51,9 +120,8 @@ impl ManagerApiMessage {
             ProtoCsPayloadType::ProtoServerTrailingSlChangedEvent => {}
             ProtoCsPayloadType::ProtoServerMarketDataEvent => {}
             ProtoCsPayloadType::ErrorRes => {
-                let payload = payload.as_ref().unwrap();
                 return Ok(Some(ManagerApiMessage::Error(ManagerApiError::ErrorRes(
-                    prost::Message::decode(&payload[..]).unwrap(),
+                    decode_payload(payload_type, &payload)?,
                 ))));
             }
             ProtoCsPayloadType::HeartbeatEvent => return Ok(None),
@@ -72,29 +140,38 @@ impl ManagerApiMessage {
             ProtoCsPayloadType::ProtoTrendbarListReq => {}
             ProtoCsPayloadType::ProtoTrendbarListRes => {}
             ProtoCsPayloadType::ProtoOrderErrorEvent => {
-                let payload = payload.as_ref().unwrap();
                 return Ok(Some(ManagerApiMessage::Error(
-                    ManagerApiError::OrderErrorEvent(prost::Message::decode(&payload[..]).unwrap()),
+                    ManagerApiError::OrderErrorEvent(decode_payload(payload_type, &payload)?),
                 )));
             }
             ProtoCsPayloadType::ProtoVersionReq => {}
-            ProtoCsPayloadType::ProtoVersionRes => {}
+            ProtoCsPayloadType::ProtoVersionRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::VersionRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoManagerByIdReq => {}
-            ProtoCsPayloadType::ProtoManagerByIdRes => {}
+            ProtoCsPayloadType::ProtoManagerByIdRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::ManagerByIdRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoManagerLightTraderListReq => {}
             ProtoCsPayloadType::ProtoManagerLightTraderListRes => {}
             ProtoCsPayloadType::ProtoExecutionEvent => {
-                let payload = payload.as_ref().unwrap();
                 return Ok(Some(ManagerApiMessage::Event(
-                    ManagerApiEvent::ExecutionEvent(prost::Message::decode(&payload[..]).unwrap()),
+                    ManagerApiEvent::ExecutionEvent(decode_payload(payload_type, &payload)?),
                 )));
             }
             ProtoCsPayloadType::ProtoManagerAuthReq => {}
             ProtoCsPayloadType::ProtoManagerAuthRes => {
-                let payload = payload.as_ref().unwrap();
                 return Ok(Some(ManagerApiMessage::Response(
                     ManagerApiResponse::ManagerAuthRes(
-                        prost::Message::decode(&payload[..]).unwrap(),
+                        decode_payload(payload_type, &payload)?,
                     ),
                 )));
             }
@@ -107,23 +184,52 @@ impl ManagerApiMessage {
             ProtoCsPayloadType::ProtoCheckManagerPasswordReq => {}
             ProtoCsPayloadType::ProtoCheckManagerPasswordRes => {}
             ProtoCsPayloadType::ProtoServerTimeReq => {}
-            ProtoCsPayloadType::ProtoServerTimeRes => {}
+            ProtoCsPayloadType::ProtoServerTimeRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::ServerTimeRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoOrderDetailsReq => {}
             ProtoCsPayloadType::ProtoOrderDetailsRes => {
-                let payload = payload.as_ref().unwrap();
                 return Ok(Some(ManagerApiMessage::Response(
                     ManagerApiResponse::OrderDetailsRes(
-                        prost::Message::decode(&payload[..]).unwrap(),
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
+            ProtoCsPayloadType::ProtoPositionMarginChangedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::MarginChangedEvent(
+                        decode_payload(payload_type, &payload)?,
                     ),
                 )));
             }
-            ProtoCsPayloadType::ProtoPositionMarginChangedEvent => {}
             ProtoCsPayloadType::ProtoRecalculateAccountMarginReq => {}
-            ProtoCsPayloadType::ProtoRecalculateAccountMarginRes => {}
+            ProtoCsPayloadType::ProtoRecalculateAccountMarginRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::RecalculateAccountMarginRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoRecalculateSymbolMarginReq => {}
-            ProtoCsPayloadType::ProtoRecalculateSymbolMarginRes => {}
+            ProtoCsPayloadType::ProtoRecalculateSymbolMarginRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::RecalculateSymbolMarginRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoRecalculateDynamicLeverageReq => {}
-            ProtoCsPayloadType::ProtoRecalculateDynamicLeverageRes => {}
+            ProtoCsPayloadType::ProtoRecalculateDynamicLeverageRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::RecalculateDynamicLeverageMarginRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoManagerBalanceTransferReq => {}
             ProtoCsPayloadType::ProtoManagerBalanceTransferRes => {}
             ProtoCsPayloadType::ProtoCrudScheduleProfileReq => {}
@@ -166,10 +272,9 @@ impl ManagerApiMessage {
             ProtoCsPayloadType::ProtoHolidayProfileChangedEvent => {}
             ProtoCsPayloadType::ProtoTraderListReq => {}
             ProtoCsPayloadType::ProtoTraderListRes => {
-                let payload = payload.as_ref().unwrap();
                 return Ok(Some(ManagerApiMessage::Response(
                     ManagerApiResponse::TraderListRes(
-                        prost::Message::decode(&payload[..]).unwrap(),
+                        decode_payload(payload_type, &payload)?,
                     ),
                 )));
             }
@@ -178,30 +283,71 @@ impl ManagerApiMessage {
             ProtoCsPayloadType::ProtoPendingOrderListReq => {}
             ProtoCsPayloadType::ProtoPendingOrderListRes => {}
             ProtoCsPayloadType::ProtoManagerListReq => {}
-            ProtoCsPayloadType::ProtoManagerListRes => {}
+            ProtoCsPayloadType::ProtoManagerListRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::ManagerListRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoBalanceHistoryListReq => {}
             ProtoCsPayloadType::ProtoBalanceHistoryListRes => {
-                let payload = payload.as_ref().unwrap();
                 return Ok(Some(ManagerApiMessage::Response(
                     ManagerApiResponse::BalanceHistoryRes(
-                        prost::Message::decode(&payload[..]).unwrap(),
+                        decode_payload(payload_type, &payload)?,
                     ),
                 )));
             }
             ProtoCsPayloadType::ProtoExposureSymbolListReq => {}
-            ProtoCsPayloadType::ProtoExposureSymbolListRes => {}
+            ProtoCsPayloadType::ProtoExposureSymbolListRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::ExposureSymbolListRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoServerSettingsReq => {}
-            ProtoCsPayloadType::ProtoServerSettingsRes => {}
+            ProtoCsPayloadType::ProtoServerSettingsRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::ServerSettingsRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoPriceStreamListReq => {}
-            ProtoCsPayloadType::ProtoPriceStreamListRes => {}
+            ProtoCsPayloadType::ProtoPriceStreamListRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::PriceStreamListRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoLiquidityFeedListReq => {}
-            ProtoCsPayloadType::ProtoLiquidityFeedListRes => {}
+            ProtoCsPayloadType::ProtoLiquidityFeedListRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::LiquidityFeedListRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoManagerDealListReq => {}
             ProtoCsPayloadType::ProtoManagerDealListRes => {}
             ProtoCsPayloadType::ProtoCountryListReq => {}
-            ProtoCsPayloadType::ProtoCountryListRes => {}
+            ProtoCsPayloadType::ProtoCountryListRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::CountryListRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoAssetClassListReq => {}
-            ProtoCsPayloadType::ProtoAssetClassListRes => {}
+            ProtoCsPayloadType::ProtoAssetClassListRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::AssetClassListRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoOrderManagerListReq => {}
             ProtoCsPayloadType::ProtoOrderManagerListRes => {}
             ProtoCsPayloadType::ProtoHolidayProfileListReq => {}
@@ -211,21 +357,51 @@ impl ManagerApiMessage {
             ProtoCsPayloadType::ProtoManagerOrderListByPositionIdReq => {}
             ProtoCsPayloadType::ProtoManagerOrderListByPositionIdRes => {}
             ProtoCsPayloadType::ProtoSymbolCategoryListReq => {}
-            ProtoCsPayloadType::ProtoSymbolCategoryListRes => {}
+            ProtoCsPayloadType::ProtoSymbolCategoryListRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::SymbolCategoryListRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoAssetListReq => {}
-            ProtoCsPayloadType::ProtoAssetListRes => {}
+            ProtoCsPayloadType::ProtoAssetListRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::AssetListRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoManagerSymbolListReq => {}
             ProtoCsPayloadType::ProtoManagerSymbolListRes => {}
             ProtoCsPayloadType::ProtoDynamicLeverageListReq => {}
-            ProtoCsPayloadType::ProtoDynamicLeverageListRes => {}
+            ProtoCsPayloadType::ProtoDynamicLeverageListRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::DynamicLeverageListRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoGslScheduleListReq => {}
-            ProtoCsPayloadType::ProtoGslScheduleListRes => {}
+            ProtoCsPayloadType::ProtoGslScheduleListRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::GslScheduleListRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoLightGroupListReq => {}
             ProtoCsPayloadType::ProtoLightGroupListRes => {}
             ProtoCsPayloadType::ProtoGroupByIdReq => {}
             ProtoCsPayloadType::ProtoGroupByIdRes => {}
             ProtoCsPayloadType::ProtoLiquidityFeedSymbolListReq => {}
-            ProtoCsPayloadType::ProtoLiquidityFeedSymbolListRes => {}
+            ProtoCsPayloadType::ProtoLiquidityFeedSymbolListRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::LiquidityFeedSymbolListRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoLightSwapAndDividendProfileListReq => {}
             ProtoCsPayloadType::ProtoLightSwapAndDividendProfileListRes => {}
             ProtoCsPayloadType::ProtoSwapAndDividendProfileByIdReq => {}
@@ -233,10 +409,9 @@ impl ManagerApiMessage {
             ProtoCsPayloadType::ProtoCrudTraderReq => {}
             ProtoCsPayloadType::ProtoCrudTraderRes => {}
             ProtoCsPayloadType::ProtoTraderChangedEvent => {
-                let payload = payload.as_ref().unwrap();
                 return Ok(Some(ManagerApiMessage::Event(
                     ManagerApiEvent::TraderChangedEvent(
-                        prost::Message::decode(&payload[..]).unwrap(),
+                        decode_payload(payload_type, &payload)?,
                     ),
                 )));
             }
@@ -246,48 +421,156 @@ impl ManagerApiMessage {
             ProtoCsPayloadType::ProtoCrudSymbolReq => {}
             ProtoCsPayloadType::ProtoCrudSymbolRes => {}
             ProtoCsPayloadType::ProtoCrudManagerReq => {}
-            ProtoCsPayloadType::ProtoCrudManagerRes => {}
-            ProtoCsPayloadType::ProtoManagerChangedEvent => {}
+            ProtoCsPayloadType::ProtoCrudManagerRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::CrudManagerRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
+            ProtoCsPayloadType::ProtoManagerChangedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::ManagerChangedEvent(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoCrudSwapAndDividendProfileReq => {}
             ProtoCsPayloadType::ProtoCrudSwapAndDividendProfileRes => {}
             ProtoCsPayloadType::ProtoSwapAndDividendProfileChangedEvent => {}
             ProtoCsPayloadType::ProtoUpdateServerSettingsReq => {}
-            ProtoCsPayloadType::ProtoUpdateServerSettingsRes => {}
-            ProtoCsPayloadType::ProtoServerSettingsChangedEvent => {}
+            ProtoCsPayloadType::ProtoUpdateServerSettingsRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::UpdateServerSettingsRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
+            ProtoCsPayloadType::ProtoServerSettingsChangedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::ServerSettingsChangedEvent(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoChangeBalanceReq => {}
             ProtoCsPayloadType::ProtoChangeBalanceRes => {}
             ProtoCsPayloadType::ProtoPriceStreamCreateReq => {}
-            ProtoCsPayloadType::ProtoPriceStreamCreateRes => {}
+            ProtoCsPayloadType::ProtoPriceStreamCreateRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::CreatePriceStreamRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoPriceStreamDeleteReq => {}
-            ProtoCsPayloadType::ProtoPriceStreamDeleteRes => {}
+            ProtoCsPayloadType::ProtoPriceStreamDeleteRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::DeletePriceStreamRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoPriceStreamUpdateReq => {}
-            ProtoCsPayloadType::ProtoPriceStreamUpdateRes => {}
-            ProtoCsPayloadType::ProtoPriceStreamChangedEvent => {}
+            ProtoCsPayloadType::ProtoPriceStreamUpdateRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::UpdatePriceStreamRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
+            ProtoCsPayloadType::ProtoPriceStreamChangedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::PriceStreamChangedEvent(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoCrudAssetReq => {}
             ProtoCsPayloadType::ProtoCrudAssetRes => {}
-            ProtoCsPayloadType::ProtoAssetChangedEvent => {}
+            ProtoCsPayloadType::ProtoAssetChangedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::AssetChangedEvent(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoCrudLiquidityFeedSymbolReq => {}
-            ProtoCsPayloadType::ProtoCrudLiquidityFeedSymbolRes => {}
-            ProtoCsPayloadType::ProtoLiquidityFeedSymbolChangedEvent => {}
+            ProtoCsPayloadType::ProtoCrudLiquidityFeedSymbolRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::CrudLiquidityFeedSymbolRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
+            ProtoCsPayloadType::ProtoLiquidityFeedSymbolChangedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::LiquidityFeedSymbolChangedEvent(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoInsertTrendbarReq => {}
             ProtoCsPayloadType::ProtoInsertTrendbarRes => {}
             ProtoCsPayloadType::ProtoManagerSymbolChangedEvent => {}
             ProtoCsPayloadType::ProtoCrudDynamicLeverageReq => {}
-            ProtoCsPayloadType::ProtoCrudDynamicLeverageRes => {}
-            ProtoCsPayloadType::ProtoDynamicLeverageChangedEvent => {}
+            ProtoCsPayloadType::ProtoCrudDynamicLeverageRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::CrudDynamicLeverageRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
+            ProtoCsPayloadType::ProtoDynamicLeverageChangedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::DynamicLeverageChangedEvent(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoTraderPermissionLoseEvent => {}
             ProtoCsPayloadType::ProtoCrudGslScheduleReq => {}
-            ProtoCsPayloadType::ProtoCrudGslScheduleRes => {}
-            ProtoCsPayloadType::ProtoGslScheduleChangedEvent => {}
+            ProtoCsPayloadType::ProtoCrudGslScheduleRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::CrudGslScheduleRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
+            ProtoCsPayloadType::ProtoGslScheduleChangedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::GslScheduleChangedEvent(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoCreateSymbolReq => {}
             ProtoCsPayloadType::ProtoCreateSymbolRes => {}
             ProtoCsPayloadType::ProtoSymbolArchivedEvent => {}
             ProtoCsPayloadType::ProtoSymbolRestoredEvent => {}
             ProtoCsPayloadType::ProtoCrudTradeNotificationProfileReq => {}
-            ProtoCsPayloadType::ProtoCrudTradeNotificationProfileRes => {}
-            ProtoCsPayloadType::ProtoCrudTradeNotificationProfileChangedEvent => {}
+            ProtoCsPayloadType::ProtoCrudTradeNotificationProfileRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::CrudTradeNotificationProfileRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
+            ProtoCsPayloadType::ProtoCrudTradeNotificationProfileChangedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::TradeNotificationProfileChangedEvent(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoCrudTradeNotificationProfileListReq => {}
-            ProtoCsPayloadType::ProtoCrudTradeNotificationProfileListRes => {}
+            ProtoCsPayloadType::ProtoCrudTradeNotificationProfileListRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::TradeNotificationProfileListRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoDeleteTrendbarReq => {}
             ProtoCsPayloadType::ProtoDeleteTrendbarRes => {}
             ProtoCsPayloadType::ProtoSubscribeSpotQuotesReq => {}
@@ -301,18 +584,16 @@ impl ManagerApiMessage {
             ProtoCsPayloadType::ProtoManagerClosedPositionListReq => {}
             ProtoCsPayloadType::ProtoManagerClosedPositionListRes => {}
             ProtoCsPayloadType::ProtoTraderLogonEvent => {
-                let payload = payload.as_ref().unwrap();
                 return Ok(Some(ManagerApiMessage::Event(
                     ManagerApiEvent::TraderLogonEvent(
-                        prost::Message::decode(&payload[..]).unwrap(),
+                        decode_payload(payload_type, &payload)?,
                     ),
                 )));
             }
             ProtoCsPayloadType::ProtoTraderLogoutEvent => {
-                let payload = payload.as_ref().unwrap();
                 return Ok(Some(ManagerApiMessage::Event(
                     ManagerApiEvent::TraderLogoutEvent(
-                        prost::Message::decode(&payload[..]).unwrap(),
+                        decode_payload(payload_type, &payload)?,
                     ),
                 )));
             }
@@ -330,11 +611,29 @@ impl ManagerApiMessage {
             ProtoCsPayloadType::ProtoBonusHistoryListReq => {}
             ProtoCsPayloadType::ProtoBonusHistoryListRes => {}
             ProtoCsPayloadType::ProtoLiquidityFeedStatusReq => {}
-            ProtoCsPayloadType::ProtoLiquidityFeedStatusRes => {}
+            ProtoCsPayloadType::ProtoLiquidityFeedStatusRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::LiquidityFeedStatusRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoDealingSettingsReq => {}
-            ProtoCsPayloadType::ProtoDealingSettingsRes => {}
+            ProtoCsPayloadType::ProtoDealingSettingsRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::DealingSettingsRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoUpdateDealingSettingsReq => {}
-            ProtoCsPayloadType::ProtoUpdateDealingSettingsRes => {}
+            ProtoCsPayloadType::ProtoUpdateDealingSettingsRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::UpdateDealingSettingsRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoNewManualDealEvent => {}
             ProtoCsPayloadType::ProtoManualDealListReq => {}
             ProtoCsPayloadType::ProtoManualDealListRes => {}
@@ -356,22 +655,82 @@ impl ManagerApiMessage {
             ProtoCsPayloadType::ProtoDealerCancelOrderReq => {}
             ProtoCsPayloadType::ProtoDealerAmendPositionReq => {}
             ProtoCsPayloadType::ProtoDealerClosePositionReq => {}
-            ProtoCsPayloadType::ProtoDealingSettingsUpdatedEvent => {}
-            ProtoCsPayloadType::ProtoAssetClassChangedEvent => {}
-            ProtoCsPayloadType::ProtoAssetClassDeletedEvent => {}
-            ProtoCsPayloadType::ProtoSymbolCategoryChangedEvent => {}
-            ProtoCsPayloadType::ProtoSymbolCategoryDeletedEvent => {}
+            ProtoCsPayloadType::ProtoDealingSettingsUpdatedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::DealingSettingsUpdatedEvent(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
+            ProtoCsPayloadType::ProtoAssetClassChangedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::AssetClassChangedEvent(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
+            ProtoCsPayloadType::ProtoAssetClassDeletedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::AssetClassDeletedEvent(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
+            ProtoCsPayloadType::ProtoSymbolCategoryChangedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::SymbolCategoryChangedEvent(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
+            ProtoCsPayloadType::ProtoSymbolCategoryDeletedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::SymbolCategoryDeletedEvent(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoManagerGetAuthTokenReq => {}
-            ProtoCsPayloadType::ProtoManagerGetAuthTokenRes => {}
+            ProtoCsPayloadType::ProtoManagerGetAuthTokenRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::ManagerGetAuthTokenRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoSymbolsForConversionReq => {}
-            ProtoCsPayloadType::ProtoSymbolsForConversionRes => {}
+            ProtoCsPayloadType::ProtoSymbolsForConversionRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::SymbolsForConversionRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoForceClosePositionReq => {}
             ProtoCsPayloadType::ProtoForceOpenPositionReq => {}
             ProtoCsPayloadType::ProtoCrudMaxAutoExecutionSizeProfileReq => {}
-            ProtoCsPayloadType::ProtoCrudMaxAutoExecutionSizeProfileRes => {}
-            ProtoCsPayloadType::ProtoCrudMaxAutoExecutionSizeProfileChangedEvent => {}
+            ProtoCsPayloadType::ProtoCrudMaxAutoExecutionSizeProfileRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::CrudMaxAutoExecutionSizeProfileRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
+            ProtoCsPayloadType::ProtoCrudMaxAutoExecutionSizeProfileChangedEvent => {
+                return Ok(Some(ManagerApiMessage::Event(
+                    ManagerApiEvent::MaxAutoExecutionSizeProfileChangedEvent(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoCrudMaxAutoExecutionSizeProfileListReq => {}
-            ProtoCsPayloadType::ProtoCrudMaxAutoExecutionSizeProfileListRes => {}
+            ProtoCsPayloadType::ProtoCrudMaxAutoExecutionSizeProfileListRes => {
+                return Ok(Some(ManagerApiMessage::Response(
+                    ManagerApiResponse::MaxAutoExecutionSizeProfileListRes(
+                        decode_payload(payload_type, &payload)?,
+                    ),
+                )));
+            }
             ProtoCsPayloadType::ProtoHelloEvent => {
                 return Ok(Some(ManagerApiMessage::Event(ManagerApiEvent::HelloEvent)))
             }
@@ -383,3 +742,41 @@ impl ManagerApiMessage {
         ))
     }
 }
+
+/// Returns `Err` instead of panicking when the frame is malformed.
+fn decode_payload<M: prost::Message + Default>(
+    payload_type: ProtoCsPayloadType,
+    payload: &Option<Vec<u8>>,
+) -> Result<M, String> {
+    let Some(payload) = payload else {
+        return Err(format!("Payload of {} is missing", payload_type.as_str_name()));
+    };
+
+    M::decode(&payload[..]).map_err(|err| {
+        format!(
+            "Failed to decode payload of {}: {}",
+            payload_type.as_str_name(),
+            err
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::common_messages_external::ProtoMessage;
+    use crate::manager::cs_messages_external::ProtoCsPayloadType;
+    use crate::manager::models::ManagerApiMessage;
+
+    #[test]
+    fn returns_error_for_malformed_payload() {
+        for payload in [None, Some(vec![0xff, 0xff, 0xff])] {
+            let proto = ProtoMessage {
+                payload_type: ProtoCsPayloadType::ProtoExecutionEvent as u32,
+                payload,
+                client_msg_id: None,
+            };
+
+            assert!(ManagerApiMessage::try_from_proto(proto).is_err());
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use crate::manager::event_bus::OverflowPolicy;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+// long enough for the tcp client to never reconnect by itself
+const DISABLED_RECONNECT_TIMEOUT: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Exponential backoff between reconnect attempts.
+#[derive(Debug, Clone)]
+pub struct ReconnectBackoff {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    /// Part of the delay (0.0..=1.0) that is randomly added or subtracted.
+    pub jitter: f64,
+    /// After this count of failed attempts the client gives up. `None` means no limit.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectBackoff {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectBackoff {
+    /// Returns the delay before the attempt with the specified zero-based index.
+    pub fn get_delay(&self, attempt: u32) -> Duration {
+        let delay = self.get_delay_without_jitter(attempt).as_secs_f64();
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        // random value in the range -1.0..=1.0
+        let random =
+            RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64 * 2.0 - 1.0;
+
+        Duration::from_secs_f64((delay + delay * jitter * random).max(0.0))
+    }
+
+    pub fn get_delay_without_jitter(&self, attempt: u32) -> Duration {
+        let delay =
+            self.initial_delay.as_secs_f64() * self.multiplier.max(1.0).powi(attempt as i32);
+
+        Duration::from_secs_f64(delay.min(self.max_delay.as_secs_f64()))
+    }
+
+    pub fn is_exhausted(&self, attempts: u32) -> bool {
+        self.max_attempts
+            .is_some_and(|max_attempts| attempts >= max_attempts)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ManagerApiClientOptions {
+    /// Max time to wait for a connection and for a response to a request.
+    pub wait_timeout: Duration,
+    /// The connection is considered lost if nothing is received during this time.
+    pub disconnect_timeout: Duration,
+    /// Delay between reconnect attempts when `reconnect_backoff` is not set.
+    pub reconnect_timeout: Duration,
+    pub ping_interval: Duration,
+    /// Interval of checking whether the connection is established.
+    pub connect_poll_interval: Duration,
+    /// Delay after the authorization is confirmed before the connection is used.
+    pub settle_delay: Duration,
+    /// When set, the client reconnects with exponential backoff instead of the fixed
+    /// `reconnect_timeout` and gives up after `max_attempts`. Every attempt starts a new tcp
+    /// client, its own reconnects are disabled.
+    pub reconnect_backoff: Option<ReconnectBackoff>,
+    /// How often the client checks whether the primary endpoint recovered while connected to
+    /// a backup one (see `primary_probe_interval`), or switches to the next endpoint while
+    /// disconnected.
+    pub failover_check_interval: Duration,
+    /// Min interval between probes of the primary endpoint while connected to a backup one.
+    /// A probe authenticates a second session with the same manager credentials, which is
+    /// closed right after the response, so cServer sees a short extra logon of the manager.
+    pub primary_probe_interval: Duration,
+    /// Max count of received messages waiting for the handler and the event bus.
+    pub handler_queue_capacity: usize,
+    /// What to do with a received message when the handler queue is full. `Block` stalls
+    /// reading of the socket, so the connection may be lost by the disconnect timeout.
+    /// `Disconnect` drops the queued messages and the connection.
+    pub handler_overflow_policy: OverflowPolicy,
+}
+
+impl Default for ManagerApiClientOptions {
+    fn default() -> Self {
+        Self {
+            wait_timeout: Duration::from_secs(30),
+            disconnect_timeout: Duration::from_secs(40),
+            reconnect_timeout: Duration::from_secs(20),
+            ping_interval: Duration::from_secs(10),
+            connect_poll_interval: Duration::from_millis(250),
+            settle_delay: Duration::from_millis(500),
+            reconnect_backoff: None,
+            failover_check_interval: Duration::from_secs(60),
+            primary_probe_interval: Duration::from_secs(300),
+            handler_queue_capacity: 10_000,
+            handler_overflow_policy: OverflowPolicy::DropOldest,
+        }
+    }
+}
+
+impl ManagerApiClientOptions {
+    pub fn with_wait_timeout(mut self, wait_timeout: Duration) -> Self {
+        self.wait_timeout = wait_timeout;
+        self
+    }
+
+    pub fn with_disconnect_timeout(mut self, disconnect_timeout: Duration) -> Self {
+        self.disconnect_timeout = disconnect_timeout;
+        self
+    }
+
+    pub fn with_reconnect_timeout(mut self, reconnect_timeout: Duration) -> Self {
+        self.reconnect_timeout = reconnect_timeout;
+        self
+    }
+
+    /// The interval is rounded down to seconds, but not less than 1 second.
+    pub fn with_ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.ping_interval = ping_interval;
+        self
+    }
+
+    pub fn with_connect_poll_interval(mut self, connect_poll_interval: Duration) -> Self {
+        self.connect_poll_interval = connect_poll_interval;
+        self
+    }
+
+    pub fn with_settle_delay(mut self, settle_delay: Duration) -> Self {
+        self.settle_delay = settle_delay;
+        self
+    }
+
+    pub fn with_reconnect_backoff(mut self, reconnect_backoff: ReconnectBackoff) -> Self {
+        self.reconnect_backoff = Some(reconnect_backoff);
+        self
+    }
+
+    pub fn with_failover_check_interval(mut self, failover_check_interval: Duration) -> Self {
+        self.failover_check_interval = failover_check_interval;
+        self
+    }
+
+    pub fn with_primary_probe_interval(mut self, primary_probe_interval: Duration) -> Self {
+        self.primary_probe_interval = primary_probe_interval;
+        self
+    }
+
+    /// `capacity` must be greater than 0.
+    pub fn with_handler_queue(mut self, capacity: usize, policy: OverflowPolicy) -> Self {
+        self.handler_queue_capacity = capacity;
+        self.handler_overflow_policy = policy;
+        self
+    }
+
+    pub fn get_seconds_to_ping(&self) -> usize {
+        self.ping_interval.as_secs().max(1) as usize
+    }
+
+    /// Reconnect timeout of the tcp client. With the reconnect backoff the client must not
+    /// reconnect by itself, otherwise its attempts are not counted by the backoff.
+    pub fn get_tcp_reconnect_timeout(&self) -> Duration {
+        if self.reconnect_backoff.is_some() {
+            DISABLED_RECONNECT_TIMEOUT
+        } else {
+            self.reconnect_timeout
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::options::{
+        ManagerApiClientOptions, ReconnectBackoff, DISABLED_RECONNECT_TIMEOUT,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn calculates_backoff_delay() {
+        let backoff = ReconnectBackoff {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            multiplier: 2.0,
+            jitter: 0.5,
+            max_attempts: Some(3),
+        };
+
+        assert_eq!(
+            backoff.get_delay_without_jitter(0),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            backoff.get_delay_without_jitter(2),
+            Duration::from_millis(400)
+        );
+        assert_eq!(backoff.get_delay_without_jitter(10), Duration::from_secs(1));
+
+        let delay = backoff.get_delay(1);
+        assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(300));
+        assert!(!backoff.is_exhausted(2));
+        assert!(backoff.is_exhausted(3));
+    }
+
+    #[test]
+    fn disables_tcp_reconnect_with_backoff() {
+        let options = ManagerApiClientOptions::default();
+        assert_eq!(
+            options.get_tcp_reconnect_timeout(),
+            options.reconnect_timeout
+        );
+
+        let options = options.with_reconnect_backoff(ReconnectBackoff::default());
+        assert_eq!(
+            options.get_tcp_reconnect_timeout(),
+            DISABLED_RECONNECT_TIMEOUT
+        );
+    }
+}
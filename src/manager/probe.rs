@@ -0,0 +1,152 @@
+use crate::manager::common_messages_external::ProtoMessage;
+use crate::manager::cs_messages_external::{ProtoCsPayloadType, ProtoManagerAuthReq};
+use crate::manager::models::ManagerApiMessage;
+use crate::manager::relay::{LocalRelay, RelayTarget};
+use crate::manager::serialization::get_frame_len;
+use crate::redaction::redact;
+use rust_extensions::Logger;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Connects to the target through a dedicated relay, sends the auth request and waits for
+/// the response. Other messages before the response are skipped.
+pub async fn probe_endpoint(
+    target: RelayTarget,
+    auth_req: ProtoManagerAuthReq,
+    logger: Arc<dyn Logger + Send + Sync + 'static>,
+) -> Result<(), String> {
+    let relay = LocalRelay::start(logger).await?;
+    relay.set_target(target).await;
+    let mut stream = TcpStream::connect(relay.get_local_addr())
+        .await
+        .map_err(|e| format!("Failed to connect to local relay: {}", e))?;
+
+    let message = ProtoMessage::new(auth_req, ProtoCsPayloadType::ProtoManagerAuthReq)
+        .map_err(|e| format!("Failed to create auth request: {:?}", e))?;
+    write_frame(&mut stream, &message).await?;
+
+    loop {
+        let message = read_frame(&mut stream).await?;
+
+        if message.payload_type == ProtoCsPayloadType::ProtoManagerAuthRes as u32 {
+            return Ok(());
+        }
+
+        if let Ok(Some(ManagerApiMessage::Error(err))) = ManagerApiMessage::try_from_proto(message)
+        {
+            return Err(redact(&format!("Authentication failed: {:?}", err)));
+        }
+    }
+}
+
+async fn write_frame(stream: &mut TcpStream, message: &ProtoMessage) -> Result<(), String> {
+    let data = prost::Message::encode_to_vec(message);
+    let mut bytes = Vec::with_capacity(4 + data.len());
+    bytes.extend((data.len() as i32).to_be_bytes());
+    bytes.extend(data);
+
+    stream
+        .write_all(&bytes)
+        .await
+        .map_err(|e| format!("Failed to send auth request: {}", e))
+}
+
+async fn read_frame(stream: &mut TcpStream) -> Result<ProtoMessage, String> {
+    let mut len_bytes = [0u8; 4];
+    stream
+        .read_exact(&mut len_bytes)
+        .await
+        .map_err(|e| format!("Connection closed before auth response: {}", e))?;
+    // a probe must not wait for a huge frame of a broken endpoint
+    let len = get_frame_len(len_bytes)
+        .map_err(|_| format!("Invalid frame length: {}", i32::from_be_bytes(len_bytes)))?;
+
+    let mut data = vec![0u8; len];
+    stream
+        .read_exact(&mut data)
+        .await
+        .map_err(|e| format!("Connection closed before auth response: {}", e))?;
+
+    prost::Message::decode(&data[..]).map_err(|e| format!("Failed to decode frame: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::common_messages_external::{ProtoErrorRes, ProtoMessage};
+    use crate::manager::cs_messages_external::{
+        ProtoCsPayloadType, ProtoManagerAuthReq, ProtoManagerAuthRes,
+    };
+    use crate::manager::probe::{probe_endpoint, read_frame, write_frame};
+    use crate::manager::relay::RelayTarget;
+    use rust_extensions::Logger;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    struct EmptyLogger;
+
+    impl Logger for EmptyLogger {
+        fn write_info(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+        fn write_warning(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+        fn write_error(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+        fn write_fatal_error(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+        fn write_debug_info(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+    }
+
+    /// Answers the first frame with the response.
+    async fn start_server(response: ProtoMessage) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let request = read_frame(&mut socket).await.unwrap();
+            assert_eq!(
+                request.payload_type,
+                ProtoCsPayloadType::ProtoManagerAuthReq as u32
+            );
+            write_frame(&mut socket, &response).await.unwrap();
+        });
+
+        addr
+    }
+
+    async fn probe(host_port: String) -> Result<(), String> {
+        let target = RelayTarget {
+            host_port,
+            proxy: None,
+            tls: None,
+        };
+
+        probe_endpoint(
+            target,
+            ProtoManagerAuthReq::default(),
+            Arc::new(EmptyLogger),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn passes_on_auth_response() {
+        let response = ProtoMessage::new(
+            ProtoManagerAuthRes::default(),
+            ProtoCsPayloadType::ProtoManagerAuthRes,
+        )
+        .unwrap();
+        let addr = start_server(response).await;
+
+        assert!(probe(addr).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn fails_on_rejected_auth() {
+        let error = ProtoErrorRes {
+            error_code: "CH_CLIENT_AUTH_FAILURE".to_string(),
+            ..Default::default()
+        };
+        let response = ProtoMessage::new(error, ProtoCsPayloadType::ErrorRes).unwrap();
+        let addr = start_server(response).await;
+
+        assert!(probe(addr).await.is_err());
+    }
+}
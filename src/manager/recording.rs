@@ -0,0 +1,430 @@
+use crate::manager::callback::ManagerApiCallbackHandler;
+use crate::manager::common_messages_external::ProtoMessage;
+use crate::manager::cs_messages_external::{ProtoCsPayloadType, ProtoManagerAuthReq};
+use crate::manager::models::{ManagerApiEvent, ManagerApiMessage};
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+const FILE_EXTENSION: &str = "mapi";
+// timestamp (8 bytes) + direction (1 byte) + length (4 bytes)
+const RECORD_HEADER_LEN: usize = 13;
+const MASKED_PASSWORD_HASH: &str = "***";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrafficDirection {
+    Inbound = 0,
+    Outbound = 1,
+}
+
+impl TryFrom<u8> for TrafficDirection {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TrafficDirection::Inbound),
+            1 => Ok(TrafficDirection::Outbound),
+            _ => Err(format!("Invalid traffic direction: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordedMessage {
+    pub timestamp_micros: i64,
+    pub direction: TrafficDirection,
+    pub message: ProtoMessage,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrafficRecorderSettings {
+    pub directory: PathBuf,
+    pub file_prefix: String,
+    /// A new file is started when the current one exceeds the size.
+    pub max_file_size: u64,
+}
+
+struct RecorderFile {
+    file: File,
+    size: u64,
+}
+
+enum RecorderCommand {
+    Write {
+        timestamp_micros: i64,
+        bytes: Vec<u8>,
+    },
+    Flush(mpsc::Sender<io::Result<()>>),
+}
+
+/// Writes every inbound and outbound `ProtoMessage` to append-only files.
+/// Every record is: timestamp in microseconds (i64), direction (u8), length of the message (u32)
+/// and the encoded `ProtoMessage`, all numbers are big-endian.
+/// Files are written by a dedicated thread, so recording does not block reading of the socket.
+/// The password hash of `ProtoManagerAuthReq` is masked before writing.
+pub struct TrafficRecorder {
+    sender: mpsc::Sender<RecorderCommand>,
+}
+
+impl TrafficRecorder {
+    pub fn new(settings: TrafficRecorderSettings) -> io::Result<Self> {
+        std::fs::create_dir_all(&settings.directory)?;
+        let (sender, receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("traffic-recorder".to_string())
+            .spawn(move || RecorderWriter::new(settings).run(receiver))?;
+
+        Ok(Self { sender })
+    }
+
+    /// Queues the message for writing. Write errors are returned by `flush`.
+    pub fn record(&self, direction: TrafficDirection, message: &ProtoMessage) -> io::Result<()> {
+        let timestamp_micros = DateTimeAsMicroseconds::now().unix_microseconds;
+        let masked_message;
+        let message = if message.payload_type == ProtoCsPayloadType::ProtoManagerAuthReq as u32 {
+            masked_message = mask_password_hash(message)?;
+            &masked_message
+        } else {
+            message
+        };
+
+        let mut message_bytes = Vec::new();
+        prost::Message::encode(message, &mut message_bytes)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        let mut bytes = Vec::with_capacity(RECORD_HEADER_LEN + message_bytes.len());
+        bytes.extend(timestamp_micros.to_be_bytes());
+        bytes.push(direction as u8);
+        bytes.extend((message_bytes.len() as u32).to_be_bytes());
+        bytes.extend(message_bytes);
+
+        self.sender
+            .send(RecorderCommand::Write {
+                timestamp_micros,
+                bytes,
+            })
+            .map_err(|_| get_writer_stopped_error())
+    }
+
+    /// Waits until the queued messages are written and flushed. Returns the first write error
+    /// since the previous flush.
+    pub fn flush(&self) -> io::Result<()> {
+        let (sender, receiver) = mpsc::channel();
+        self.sender
+            .send(RecorderCommand::Flush(sender))
+            .map_err(|_| get_writer_stopped_error())?;
+
+        receiver.recv().map_err(|_| get_writer_stopped_error())?
+    }
+}
+
+struct RecorderWriter {
+    settings: TrafficRecorderSettings,
+    current_file: Option<RecorderFile>,
+    error: Option<io::Error>,
+}
+
+impl RecorderWriter {
+    fn new(settings: TrafficRecorderSettings) -> Self {
+        Self {
+            settings,
+            current_file: None,
+            error: None,
+        }
+    }
+
+    /// Runs until the recorder is dropped.
+    fn run(mut self, receiver: mpsc::Receiver<RecorderCommand>) {
+        while let Ok(command) = receiver.recv() {
+            match command {
+                RecorderCommand::Write {
+                    timestamp_micros,
+                    bytes,
+                } => {
+                    if let Err(err) = self.write(timestamp_micros, &bytes) {
+                        self.error.get_or_insert(err);
+                    }
+                }
+                RecorderCommand::Flush(sender) => {
+                    let result = match self.error.take() {
+                        Some(err) => Err(err),
+                        None => self.flush(),
+                    };
+                    let _ = sender.send(result);
+                }
+            }
+        }
+
+        let _ = self.flush();
+    }
+
+    fn write(&mut self, timestamp_micros: i64, bytes: &[u8]) -> io::Result<()> {
+        if self
+            .current_file
+            .as_ref()
+            .is_some_and(|file| file.size >= self.settings.max_file_size)
+        {
+            self.current_file.take();
+        }
+
+        if self.current_file.is_none() {
+            let path = self.settings.directory.join(format!(
+                "{}-{:020}.{}",
+                self.settings.file_prefix, timestamp_micros, FILE_EXTENSION
+            ));
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            let size = file.metadata()?.len();
+            self.current_file.replace(RecorderFile { file, size });
+        }
+
+        let current_file = self.current_file.as_mut().expect("must exist");
+        current_file.file.write_all(bytes)?;
+        current_file.size += bytes.len() as u64;
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(current_file) = self.current_file.as_mut() {
+            current_file.file.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+fn get_writer_stopped_error() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "Traffic recorder writer stopped")
+}
+
+/// Replaces the password hash of the auth request, so credentials are not stored in the file.
+fn mask_password_hash(message: &ProtoMessage) -> io::Result<ProtoMessage> {
+    let payload = message.payload.as_deref().unwrap_or_default();
+    let mut req: ProtoManagerAuthReq = prost::Message::decode(payload)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    req.password_hash = MASKED_PASSWORD_HASH.to_string();
+    let mut masked_message = message.clone();
+    masked_message.payload = Some(prost::Message::encode_to_vec(&req));
+
+    Ok(masked_message)
+}
+
+/// Reads all records of the recording file.
+pub fn read_recording(path: &Path) -> io::Result<Vec<RecordedMessage>> {
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+    let mut records = Vec::new();
+    let mut position = 0;
+
+    while position + RECORD_HEADER_LEN <= bytes.len() {
+        let header = &bytes[position..position + RECORD_HEADER_LEN];
+        let timestamp_micros = i64::from_be_bytes(header[0..8].try_into().unwrap());
+        let direction = TrafficDirection::try_from(header[8])
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let len = u32::from_be_bytes(header[9..13].try_into().unwrap()) as usize;
+        position += RECORD_HEADER_LEN;
+
+        if position + len > bytes.len() {
+            // the last record was not written completely
+            break;
+        }
+
+        let message: ProtoMessage = prost::Message::decode(&bytes[position..position + len])
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        position += len;
+        records.push(RecordedMessage {
+            timestamp_micros,
+            direction,
+            message,
+        });
+    }
+
+    Ok(records)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// Keeps the original intervals between messages.
+    Original,
+    /// Divides the original intervals by the multiplier.
+    Accelerated(f64),
+    /// Delivers messages without delays.
+    Max,
+}
+
+/// Feeds recorded inbound messages into a handler.
+pub struct ReplaySource {
+    records: Vec<RecordedMessage>,
+}
+
+impl ReplaySource {
+    pub fn new(records: Vec<RecordedMessage>) -> Self {
+        Self { records }
+    }
+
+    /// Loads all recording files with the prefix from the directory in the recording order.
+    pub fn from_directory(directory: &Path, file_prefix: &str) -> io::Result<Self> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(directory)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension().is_some_and(|ext| ext == FILE_EXTENSION)
+                    && path.file_name().is_some_and(|name| {
+                        name.to_string_lossy()
+                            .starts_with(&format!("{}-", file_prefix))
+                    })
+            })
+            .collect();
+        paths.sort();
+        let mut records = Vec::new();
+
+        for path in paths {
+            records.extend(read_recording(&path)?);
+        }
+
+        Ok(Self::new(records))
+    }
+
+    pub fn get_records(&self) -> &[RecordedMessage] {
+        &self.records
+    }
+
+    /// Delivers inbound messages to the handler the same way `ManagerApiCallback` does,
+    /// between `on_connected` and `on_disconnected`. Returns count of delivered messages.
+    pub async fn replay<T: ManagerApiCallbackHandler + Send + Sync>(
+        &self,
+        handler: &T,
+        speed: ReplaySpeed,
+    ) -> usize {
+        let mut previous_timestamp = None;
+        let mut count = 0;
+        handler.on_connected().await;
+
+        for record in self
+            .records
+            .iter()
+            .filter(|record| record.direction == TrafficDirection::Inbound)
+        {
+            if let Some(previous_timestamp) = previous_timestamp {
+                let delay = get_delay(record.timestamp_micros - previous_timestamp, speed);
+
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+            }
+
+            previous_timestamp = Some(record.timestamp_micros);
+
+            let Ok(Some(message)) = ManagerApiMessage::try_from_proto(record.message.clone())
+            else {
+                continue;
+            };
+
+            if let ManagerApiMessage::Event(ManagerApiEvent::ExecutionEvent(event)) = &message {
+                if event.notify_big_volume == Some(true) {
+                    handler.on_big_volume_execution(event).await;
+                }
+            }
+
+            handler.on_message(message).await;
+            count += 1;
+        }
+
+        handler.on_disconnected().await;
+
+        count
+    }
+}
+
+fn get_delay(interval_micros: i64, speed: ReplaySpeed) -> Duration {
+    let interval = Duration::from_micros(interval_micros.max(0) as u64);
+
+    match speed {
+        ReplaySpeed::Original => interval,
+        ReplaySpeed::Accelerated(multiplier) if multiplier > 0.0 => interval.div_f64(multiplier),
+        ReplaySpeed::Accelerated(_) | ReplaySpeed::Max => Duration::ZERO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::callback::ManagerApiCallbackHandler;
+    use crate::manager::common_messages_external::ProtoMessage;
+    use crate::manager::cs_messages_external::{
+        ProtoCsPayloadType, ProtoHelloEvent, ProtoManagerAuthReq,
+    };
+    use crate::manager::models::ManagerApiMessage;
+    use crate::manager::recording::{
+        ReplaySource, ReplaySpeed, TrafficDirection, TrafficRecorder, TrafficRecorderSettings,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountingHandler {
+        count: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl ManagerApiCallbackHandler for CountingHandler {
+        async fn on_connected(&self) {}
+        async fn on_disconnected(&self) {}
+
+        async fn on_message(&self, _message: ManagerApiMessage) {
+            self.count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn records_and_replays_traffic() {
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let recorder = TrafficRecorder::new(TrafficRecorderSettings {
+            directory: directory.clone(),
+            file_prefix: "test".to_string(),
+            max_file_size: 1,
+        })
+        .unwrap();
+        let message = ProtoMessage::new(
+            ProtoHelloEvent::default(),
+            ProtoCsPayloadType::ProtoHelloEvent,
+        )
+        .unwrap();
+
+        for direction in [
+            TrafficDirection::Inbound,
+            TrafficDirection::Outbound,
+            TrafficDirection::Inbound,
+        ] {
+            recorder.record(direction, &message).unwrap();
+            // file names are based on the timestamp
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        }
+
+        let auth_req = ProtoManagerAuthReq {
+            payload_type: None,
+            plant_id: "plant".to_string(),
+            environment_name: "demo".to_string(),
+            login: 1,
+            password_hash: "secret-hash".to_string(),
+        };
+        let auth_message =
+            ProtoMessage::new(auth_req, ProtoCsPayloadType::ProtoManagerAuthReq).unwrap();
+        recorder
+            .record(TrafficDirection::Outbound, &auth_message)
+            .unwrap();
+        recorder.flush().unwrap();
+
+        let source = ReplaySource::from_directory(&directory, "test").unwrap();
+        let handler = CountingHandler::default();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(source.get_records().len(), 4);
+        let recorded_auth_req: ProtoManagerAuthReq =
+            prost::Message::decode(source.get_records()[3].message.payload.as_deref().unwrap())
+                .unwrap();
+        assert_eq!(recorded_auth_req.password_hash, "***");
+        assert_eq!(source.replay(&handler, ReplaySpeed::Max).await, 2);
+        assert_eq!(handler.count.load(Ordering::SeqCst), 2);
+    }
+}
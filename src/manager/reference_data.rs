@@ -0,0 +1,251 @@
+use crate::manager::cs_messages_external::{
+    ProtoAsset, ProtoAssetClass, ProtoCountry, ProtoCrudOperation, ProtoLightSymbol,
+    ProtoSymbolCategory,
+};
+use crate::manager::models::ManagerApiEvent;
+use crate::webservices::{SymbolModel, TraderContactDetails};
+use std::collections::HashMap;
+
+/// Cache of the cServer reference entities. It is loaded by `ManagerApiClient` after login
+/// and kept current from the changed and deleted events.
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceData {
+    countries: HashMap<i64, ProtoCountry>,
+    assets: HashMap<i64, ProtoAsset>,
+    asset_classes: HashMap<i64, ProtoAssetClass>,
+    symbol_categories: HashMap<i64, ProtoSymbolCategory>,
+    /// Conversion chains by first and last asset ids.
+    symbols_for_conversion: HashMap<(i64, i64), Vec<ProtoLightSymbol>>,
+}
+
+impl ReferenceData {
+    pub fn set_countries(&mut self, countries: Vec<ProtoCountry>) {
+        self.countries = countries
+            .into_iter()
+            .map(|country| (country.country_id, country))
+            .collect();
+    }
+
+    pub fn set_assets(&mut self, assets: Vec<ProtoAsset>) {
+        self.assets = assets
+            .into_iter()
+            .map(|asset| (asset.asset_id, asset))
+            .collect();
+    }
+
+    /// Asset classes without id are skipped.
+    pub fn set_asset_classes(&mut self, asset_classes: Vec<ProtoAssetClass>) {
+        self.asset_classes = asset_classes
+            .into_iter()
+            .filter_map(|asset_class| asset_class.id.map(|id| (id, asset_class)))
+            .collect();
+    }
+
+    pub fn set_symbol_categories(&mut self, symbol_categories: Vec<ProtoSymbolCategory>) {
+        self.symbol_categories = symbol_categories
+            .into_iter()
+            .map(|category| (category.id, category))
+            .collect();
+    }
+
+    pub fn set_symbols_for_conversion(
+        &mut self,
+        first_asset_id: i64,
+        last_asset_id: i64,
+        symbols: Vec<ProtoLightSymbol>,
+    ) {
+        self.symbols_for_conversion
+            .insert((first_asset_id, last_asset_id), symbols);
+    }
+
+    pub fn get_countries(&self) -> impl Iterator<Item = &ProtoCountry> {
+        self.countries.values()
+    }
+
+    pub fn get_country(&self, country_id: i64) -> Option<&ProtoCountry> {
+        self.countries.get(&country_id)
+    }
+
+    pub fn get_country_by_name(&self, name: &str) -> Option<&ProtoCountry> {
+        self.countries
+            .values()
+            .find(|country| country.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the country by ISO 3166-1 alpha-2 code.
+    pub fn get_country_by_code(&self, code: &str) -> Option<&ProtoCountry> {
+        self.countries.values().find(|country| {
+            country
+                .country_a2_code
+                .as_ref()
+                .is_some_and(|country_code| country_code.eq_ignore_ascii_case(code))
+        })
+    }
+
+    pub fn get_assets(&self) -> impl Iterator<Item = &ProtoAsset> {
+        self.assets.values()
+    }
+
+    pub fn get_asset(&self, asset_id: i64) -> Option<&ProtoAsset> {
+        self.assets.get(&asset_id)
+    }
+
+    pub fn get_asset_by_name(&self, name: &str) -> Option<&ProtoAsset> {
+        self.assets
+            .values()
+            .find(|asset| asset.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn get_asset_classes(&self) -> impl Iterator<Item = &ProtoAssetClass> {
+        self.asset_classes.values()
+    }
+
+    pub fn get_asset_class(&self, asset_class_id: i64) -> Option<&ProtoAssetClass> {
+        self.asset_classes.get(&asset_class_id)
+    }
+
+    pub fn get_asset_class_by_name(&self, name: &str) -> Option<&ProtoAssetClass> {
+        self.asset_classes.values().find(|asset_class| {
+            asset_class
+                .name
+                .as_ref()
+                .is_some_and(|asset_class_name| asset_class_name.eq_ignore_ascii_case(name))
+        })
+    }
+
+    pub fn get_symbol_categories(&self) -> impl Iterator<Item = &ProtoSymbolCategory> {
+        self.symbol_categories.values()
+    }
+
+    pub fn get_symbol_category(&self, symbol_category_id: i64) -> Option<&ProtoSymbolCategory> {
+        self.symbol_categories.get(&symbol_category_id)
+    }
+
+    pub fn get_symbol_category_by_name(&self, name: &str) -> Option<&ProtoSymbolCategory> {
+        self.symbol_categories
+            .values()
+            .find(|category| category.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn get_symbols_for_conversion(
+        &self,
+        first_asset_id: i64,
+        last_asset_id: i64,
+    ) -> Option<&Vec<ProtoLightSymbol>> {
+        self.symbols_for_conversion
+            .get(&(first_asset_id, last_asset_id))
+    }
+
+    pub fn get_trader_country(
+        &self,
+        contact_details: &TraderContactDetails,
+    ) -> Option<&ProtoCountry> {
+        contact_details
+            .country_id
+            .and_then(|country_id| self.get_country(country_id))
+    }
+
+    pub fn get_symbol_asset_class(&self, symbol: &SymbolModel) -> Option<&ProtoAssetClass> {
+        self.get_asset_class_by_name(&symbol.asset_class)
+    }
+
+    /// Symbol categories are resolved within the asset class of the symbol because
+    /// category names are unique only inside an asset class.
+    pub fn get_symbol_category_of(&self, symbol: &SymbolModel) -> Option<&ProtoSymbolCategory> {
+        let asset_class_id = self.get_symbol_asset_class(symbol)?.id?;
+
+        self.symbol_categories.values().find(|category| {
+            category.asset_class_id == asset_class_id
+                && category.name.eq_ignore_ascii_case(&symbol.category)
+        })
+    }
+
+    /// Applies a changed or deleted reference entity event.
+    /// Returns FALSE if the event does not affect reference data.
+    pub fn update(&mut self, event: &ManagerApiEvent) -> bool {
+        match event {
+            ManagerApiEvent::AssetChangedEvent(event) => {
+                if event.operation == ProtoCrudOperation::ProtoDelete as i32 {
+                    self.assets.remove(&event.asset.asset_id);
+                } else {
+                    self.assets
+                        .insert(event.asset.asset_id, event.asset.clone());
+                }
+                // conversion chains may go through the changed asset
+                self.symbols_for_conversion.clear();
+            }
+            ManagerApiEvent::AssetClassChangedEvent(event) => {
+                if let Some(id) = event.asset_class.id {
+                    self.asset_classes.insert(id, event.asset_class.clone());
+                }
+            }
+            ManagerApiEvent::AssetClassDeletedEvent(event) => {
+                self.asset_classes.remove(&event.asset_class_id);
+            }
+            ManagerApiEvent::SymbolCategoryChangedEvent(event) => {
+                self.symbol_categories
+                    .insert(event.symbol_category.id, event.symbol_category.clone());
+            }
+            ManagerApiEvent::SymbolCategoryDeletedEvent(event) => {
+                self.symbol_categories.remove(&event.symbol_category_id);
+            }
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::cs_messages_external::{
+        ProtoAssetClass, ProtoAssetClassDeletedEvent, ProtoCountry, ProtoSymbolCategory,
+    };
+    use crate::manager::models::ManagerApiEvent;
+    use crate::manager::reference_data::ReferenceData;
+
+    #[test]
+    fn resolves_and_updates_reference_entities() {
+        let mut reference_data = ReferenceData::default();
+        reference_data.set_countries(vec![ProtoCountry {
+            country_id: 1,
+            name: "Cyprus".to_string(),
+            country_a2_code: Some("CY".to_string()),
+        }]);
+        reference_data.set_asset_classes(vec![ProtoAssetClass {
+            id: Some(10),
+            name: Some("Forex".to_string()),
+            ..Default::default()
+        }]);
+        reference_data.set_symbol_categories(vec![ProtoSymbolCategory {
+            id: 100,
+            asset_class_id: 10,
+            name: "Majors".to_string(),
+            ..Default::default()
+        }]);
+
+        assert_eq!(
+            reference_data.get_country_by_code("cy").unwrap().name,
+            "Cyprus"
+        );
+        assert_eq!(
+            reference_data.get_asset_class_by_name("forex").unwrap().id,
+            Some(10)
+        );
+        assert_eq!(
+            reference_data
+                .get_symbol_category_by_name("Majors")
+                .unwrap()
+                .asset_class_id,
+            10
+        );
+
+        let event = ManagerApiEvent::AssetClassDeletedEvent(ProtoAssetClassDeletedEvent {
+            payload_type: None,
+            asset_class_id: 10,
+        });
+
+        assert!(reference_data.update(&event));
+        assert!(reference_data.get_asset_class(10).is_none());
+    }
+}
@@ -0,0 +1,411 @@
+use crate::manager::transport::parse_host_port;
+use crate::proxy::ProxyConfig;
+use crate::redaction::redact;
+use rust_extensions::Logger;
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection};
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Write};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{oneshot, RwLock};
+
+const BUFFER_SIZE: usize = 16 * 1024;
+/// Time the connection allowed by `set_target` must be made in.
+const ALLOWED_CONNECTION_TTL: Duration = Duration::from_secs(10);
+const MAX_ACCEPT_ERROR_DELAY: Duration = Duration::from_secs(1);
+
+/// Where the relay forwards the accepted connections.
+#[derive(Clone)]
+pub struct RelayTarget {
+    pub host_port: String,
+    /// When set, the connection is tunneled through the proxy.
+    pub proxy: Option<ProxyConfig>,
+    /// When set, the relay wraps the forwarded connection into TLS.
+    pub tls: Option<RelayTls>,
+}
+
+#[derive(Clone)]
+pub struct RelayTls {
+    pub server_name: ServerName<'static>,
+    pub config: Arc<ClientConfig>,
+}
+
+/// Listens on a loopback port and forwards connections to the current target. The tcp client
+/// connects to the relay when the connection needs something it can not do by itself.
+/// Dropping the relay closes the listener, the forwarded connections stay open.
+///
+/// The relay forwards with the proxy credentials and the client certificate, so it must not
+/// be usable by other local processes. It can not authenticate the connecting client, so
+/// it accepts only the connections it expects: each `set_target` call allows one connection
+/// within `ALLOWED_CONNECTION_TTL`, other connections are closed and logged. A local process
+/// that connects first within this time still gets the connection, but the expected client
+/// is rejected then, which shows up in the log.
+pub struct LocalRelay {
+    local_addr: SocketAddr,
+    target: Arc<RwLock<Option<RelayTarget>>>,
+    /// Deadlines of the allowed connections, oldest first.
+    allowed_connections: Arc<Mutex<VecDeque<Instant>>>,
+    // dropped with the relay, which stops accepting connections
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl LocalRelay {
+    /// Failed forwards are logged with the logger.
+    pub async fn start(logger: Arc<dyn Logger + Send + Sync + 'static>) -> Result<Self, String> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| format!("Failed to start local relay: {}", e))?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to start local relay: {}", e))?;
+        let target: Arc<RwLock<Option<RelayTarget>>> = Default::default();
+        let allowed_connections: Arc<Mutex<VecDeque<Instant>>> = Default::default();
+        let (shutdown, shutdown_receiver) = oneshot::channel();
+
+        tokio::spawn(accept_connections(
+            listener,
+            Arc::clone(&target),
+            Arc::clone(&allowed_connections),
+            shutdown_receiver,
+            logger,
+        ));
+
+        Ok(Self {
+            local_addr,
+            target,
+            allowed_connections,
+            _shutdown: shutdown,
+        })
+    }
+
+    /// Sets the target for the next accepted connections and allows one more connection.
+    /// Must be called right before every connection to the relay.
+    pub async fn set_target(&self, target: RelayTarget) {
+        self.target.write().await.replace(target);
+        self.allowed_connections
+            .lock()
+            .unwrap()
+            .push_back(Instant::now() + ALLOWED_CONNECTION_TTL);
+    }
+
+    pub fn get_local_addr(&self) -> String {
+        self.local_addr.to_string()
+    }
+}
+
+/// Takes the oldest allowed connection which is not expired.
+fn take_allowed_connection(allowed_connections: &Mutex<VecDeque<Instant>>) -> bool {
+    let mut allowed_connections = allowed_connections.lock().unwrap();
+    let now = Instant::now();
+
+    while let Some(deadline) = allowed_connections.pop_front() {
+        if deadline >= now {
+            return true;
+        }
+    }
+
+    false
+}
+
+async fn accept_connections(
+    listener: TcpListener,
+    target: Arc<RwLock<Option<RelayTarget>>>,
+    allowed_connections: Arc<Mutex<VecDeque<Instant>>>,
+    mut shutdown: oneshot::Receiver<()>,
+    logger: Arc<dyn Logger + Send + Sync + 'static>,
+) {
+    let mut error_delay = Duration::from_millis(10);
+
+    loop {
+        let result = tokio::select! {
+            // relay is dropped
+            _ = &mut shutdown => break,
+            result = listener.accept() => result,
+        };
+
+        let (local, peer_addr) = match result {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                // e.g. too many open files: retrying at once would spin
+                logger.write_warning(
+                    "LocalRelay.accept".into(),
+                    format!("Failed to accept connection: {}", err),
+                    None,
+                );
+                tokio::time::sleep(error_delay).await;
+                error_delay = (error_delay * 2).min(MAX_ACCEPT_ERROR_DELAY);
+                continue;
+            }
+        };
+
+        error_delay = Duration::from_millis(10);
+
+        if !take_allowed_connection(&allowed_connections) {
+            logger.write_warning(
+                "LocalRelay.accept".into(),
+                format!("Rejected unexpected connection from {}", peer_addr),
+                None,
+            );
+            continue;
+        }
+
+        let Some(target) = target.read().await.clone() else {
+            continue;
+        };
+
+        let logger = Arc::clone(&logger);
+
+        tokio::spawn(async move {
+            let host_port = target.host_port.clone();
+
+            if let Err(err) = forward(local, target).await {
+                logger.write_warning(
+                    "LocalRelay.forward".into(),
+                    redact(&format!("Failed to forward to {}: {}", host_port, err)),
+                    None,
+                );
+            }
+        });
+    }
+}
+
+async fn forward(mut local: TcpStream, target: RelayTarget) -> Result<(), String> {
+    let mut upstream = match &target.proxy {
+        Some(proxy) => {
+            let (host, port) = parse_host_port(&target.host_port)?;
+            proxy.connect(&host, port).await?
+        }
+        None => TcpStream::connect(&target.host_port)
+            .await
+            .map_err(|e| format!("Failed to connect to {}: {}", target.host_port, e))?,
+    };
+
+    let Some(tls) = target.tls else {
+        tokio::io::copy_bidirectional(&mut local, &mut upstream)
+            .await
+            .map_err(|e| e.to_string())?;
+        return Ok(());
+    };
+
+    let connection = ClientConnection::new(tls.config, tls.server_name)
+        .map_err(|e| format!("Failed to create TLS connection: {}", e))?;
+
+    forward_tls(local, upstream, connection).await
+}
+
+/// Encrypts the bytes from the local connection into the upstream and decrypts the response.
+async fn forward_tls(
+    mut local: TcpStream,
+    mut upstream: TcpStream,
+    mut connection: ClientConnection,
+) -> Result<(), String> {
+    let mut local_buffer = vec![0u8; BUFFER_SIZE];
+    let mut upstream_buffer = vec![0u8; BUFFER_SIZE];
+
+    loop {
+        while connection.wants_write() {
+            let mut data = Vec::new();
+            connection.write_tls(&mut data).map_err(|e| e.to_string())?;
+            upstream.write_all(&data).await.map_err(|e| e.to_string())?;
+        }
+
+        tokio::select! {
+            result = local.read(&mut local_buffer) => {
+                let size = result.map_err(|e| e.to_string())?;
+
+                if size == 0 {
+                    connection.send_close_notify();
+                    let mut data = Vec::new();
+                    let _ = connection.write_tls(&mut data);
+                    let _ = upstream.write_all(&data).await;
+                    return Ok(());
+                }
+
+                connection
+                    .writer()
+                    .write_all(&local_buffer[..size])
+                    .map_err(|e| e.to_string())?;
+            }
+            result = upstream.read(&mut upstream_buffer) => {
+                let size = result.map_err(|e| e.to_string())?;
+
+                if size == 0 {
+                    return Ok(());
+                }
+
+                let mut data = &upstream_buffer[..size];
+                let mut plaintext = Vec::new();
+                let mut is_closed = false;
+
+                while !data.is_empty() && !is_closed {
+                    connection.read_tls(&mut data).map_err(|e| e.to_string())?;
+                    connection
+                        .process_new_packets()
+                        .map_err(|e| format!("TLS error: {}", e))?;
+
+                    is_closed = match connection.reader().read_to_end(&mut plaintext) {
+                        Ok(_) => true,
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => false,
+                        Err(e) => return Err(e.to_string()),
+                    };
+                }
+
+                local.write_all(&plaintext).await.map_err(|e| e.to_string())?;
+
+                if is_closed {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::relay::{LocalRelay, RelayTarget};
+    use rust_extensions::Logger;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    #[derive(Default)]
+    struct WarningsLogger {
+        warnings: Mutex<Vec<String>>,
+    }
+
+    impl Logger for WarningsLogger {
+        fn write_info(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+
+        fn write_warning(&self, _: String, message: String, _: Option<HashMap<String, String>>) {
+            self.warnings.lock().unwrap().push(message);
+        }
+
+        fn write_error(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+        fn write_fatal_error(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+        fn write_debug_info(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+    }
+
+    #[tokio::test]
+    async fn forwards_connection() {
+        let upstream = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_addr = upstream.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut socket, _) = upstream.accept().await.unwrap();
+            let mut buffer = [0u8; 4];
+            socket.read_exact(&mut buffer).await.unwrap();
+            socket.write_all(&buffer).await.unwrap();
+        });
+
+        let relay = LocalRelay::start(Arc::new(WarningsLogger::default()))
+            .await
+            .unwrap();
+        relay
+            .set_target(RelayTarget {
+                host_port: upstream_addr,
+                proxy: None,
+                tls: None,
+            })
+            .await;
+
+        let mut client = TcpStream::connect(relay.get_local_addr()).await.unwrap();
+        client.write_all(b"ping").await.unwrap();
+        let mut buffer = [0u8; 4];
+        client.read_exact(&mut buffer).await.unwrap();
+
+        assert_eq!(&buffer, b"ping");
+    }
+
+    #[tokio::test]
+    async fn logs_failed_forward() {
+        // the port is free once the listener is dropped
+        let upstream = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_addr = upstream.local_addr().unwrap().to_string();
+        drop(upstream);
+
+        let logger = Arc::new(WarningsLogger::default());
+        let relay = LocalRelay::start(logger.clone()).await.unwrap();
+        relay
+            .set_target(RelayTarget {
+                host_port: upstream_addr,
+                proxy: None,
+                tls: None,
+            })
+            .await;
+
+        let _client = TcpStream::connect(relay.get_local_addr()).await.unwrap();
+        let is_logged = tokio::time::timeout(Duration::from_secs(5), async {
+            while logger.warnings.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+
+        assert!(is_logged.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_unexpected_connection() {
+        let upstream = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_addr = upstream.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = upstream.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut buffer = [0u8; 4];
+                    socket.read_exact(&mut buffer).await.unwrap();
+                    socket.write_all(&buffer).await.unwrap();
+                });
+            }
+        });
+
+        let logger = Arc::new(WarningsLogger::default());
+        let relay = LocalRelay::start(logger.clone()).await.unwrap();
+        relay
+            .set_target(RelayTarget {
+                host_port: upstream_addr,
+                proxy: None,
+                tls: None,
+            })
+            .await;
+
+        let mut expected = TcpStream::connect(relay.get_local_addr()).await.unwrap();
+        expected.write_all(b"ping").await.unwrap();
+        let mut buffer = [0u8; 4];
+        expected.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(&buffer, b"ping");
+
+        let mut unexpected = TcpStream::connect(relay.get_local_addr()).await.unwrap();
+        let _ = unexpected.write_all(b"ping").await;
+        let mut buffer = vec![];
+        let read =
+            tokio::time::timeout(Duration::from_secs(5), unexpected.read_to_end(&mut buffer)).await;
+
+        assert!(read.is_ok());
+        assert!(buffer.is_empty());
+        assert_eq!(logger.warnings.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn closes_listener_when_dropped() {
+        let relay = LocalRelay::start(Arc::new(WarningsLogger::default()))
+            .await
+            .unwrap();
+        let local_addr = relay.get_local_addr();
+        drop(relay);
+
+        let is_closed = tokio::time::timeout(Duration::from_secs(5), async {
+            while TcpStream::connect(&local_addr).await.is_ok() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+
+        assert!(is_closed.is_ok());
+    }
+}
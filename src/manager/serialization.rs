@@ -1,6 +1,8 @@
-use crate::manager::common_messages_external::{ProtoMessage, ProtoPingReq};
+use crate::manager::common_messages_external::{ProtoMessage, ProtoPingReq, ProtoPingRes};
 use crate::manager::common_model_messages_external::ProtoPayloadType;
 use crate::manager::cs_messages_external::ProtoCsPayloadType;
+use crate::manager::recording::{TrafficDirection, TrafficRecorder};
+use crate::metrics::ConnectorMetrics;
 use async_trait::async_trait;
 use my_tcp_sockets::{
     socket_reader::{ReadingTcpContractFail, SocketReader},
@@ -8,9 +10,66 @@ use my_tcp_sockets::{
 };
 use prost::EncodeError;
 use rust_extensions::date_time::DateTimeAsMicroseconds;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Default)]
-pub struct ManagerApiSerializer {}
+pub struct ManagerApiSerializer {
+    recorder: Option<Arc<TrafficRecorder>>,
+    metrics: Option<Arc<ConnectorMetrics>>,
+}
+
+impl ManagerApiSerializer {
+    fn record(&self, direction: TrafficDirection, contract: &ProtoMessage) {
+        if let Some(metrics) = &self.metrics {
+            match direction {
+                TrafficDirection::Inbound => metrics.on_message_received(contract.payload_type),
+                TrafficDirection::Outbound => metrics.on_message_sent(contract.payload_type),
+            }
+
+            if contract.payload_type == ProtoPayloadType::PingRes as u32 {
+                record_ping_rtt(metrics, contract);
+            }
+        }
+
+        if let Some(recorder) = &self.recorder {
+            // recording must not break the connection
+            let _ = recorder.record(direction, contract);
+        }
+    }
+}
+
+fn record_ping_rtt(metrics: &ConnectorMetrics, contract: &ProtoMessage) {
+    let Some(payload) = &contract.payload else {
+        return;
+    };
+
+    let Ok(res) = <ProtoPingRes as prost::Message>::decode(&payload[..]) else {
+        return;
+    };
+
+    // the response contains the timestamp of the request
+    let now_millis = DateTimeAsMicroseconds::now().unix_microseconds as u64 / 1000;
+    metrics.on_ping_rtt(Duration::from_millis(now_millis.saturating_sub(res.timestamp)));
+}
+
+/// Frames above this length are rejected instead of allocating a buffer for them.
+pub(crate) const MAX_FRAME_LEN: usize = 1024 * 1024;
+
+/// Returns the length of the frame data from the 4 byte big-endian prefix.
+pub(crate) fn get_frame_len(len_bytes: [u8; 4]) -> Result<usize, ReadingTcpContractFail> {
+    let len = i32::from_be_bytes(len_bytes);
+
+    if len < 0 {
+        return Err(ReadingTcpContractFail::ErrorReadingSize);
+    }
+
+    if len as usize > MAX_FRAME_LEN {
+        return Err(ReadingTcpContractFail::IncomingPacketTooBig);
+    }
+
+    Ok(len as usize)
+}
 
 pub struct ManagerApiSerializerState {}
 
@@ -57,6 +116,7 @@ impl TcpSocketSerializer<ProtoMessage, ManagerApiSerializerState> for ManagerApi
         bytes.extend(len_bytes);
         bytes.extend(data_bytes);
         out.write_slice(&bytes[..]);
+        self.record(TrafficDirection::Outbound, contract);
     }
 
     fn get_ping(&self) -> ProtoMessage {
@@ -85,12 +145,18 @@ impl TcpSocketSerializer<ProtoMessage, ManagerApiSerializerState> for ManagerApi
         // The message which follows is always wrapped within the ProtoMessage structure.
         let mut len_buff = [0; 4];
         socket_reader.read_buf(&mut len_buff).await?;
-        let len = i32::from_be_bytes(len_buff) as usize;
-        let mut data_buf = Vec::with_capacity(len);
-        // safety: we created vec with specified capacity with the length according to the protocol
-        unsafe { data_buf.set_len(len) }
+        let len = get_frame_len(len_buff)?;
+        let mut data_buf = vec![0; len];
         socket_reader.read_buf(&mut data_buf[..]).await?;
-        let message: ProtoMessage = prost::Message::decode(&data_buf[..]).unwrap();
+        let message = prost::Message::decode(&data_buf[..]).map_err(|_| {
+            if let Some(metrics) = &self.metrics {
+                metrics.on_decode_failure();
+            }
+
+            // the stream can not be trusted after a malformed frame, so the connection is dropped
+            ReadingTcpContractFail::SocketDisconnected
+        })?;
+        self.record(TrafficDirection::Inbound, &message);
 
         Ok(message)
     }
@@ -107,17 +173,56 @@ impl TcpContract for ProtoMessage {
 }
 
 #[derive(Default)]
-pub struct ManagerApiSerializerFactory {}
+pub struct ManagerApiSerializerFactory {
+    recorder: Option<Arc<TrafficRecorder>>,
+    metrics: Option<Arc<ConnectorMetrics>>,
+}
+
+impl ManagerApiSerializerFactory {
+    /// Creates serializers that write all inbound and outbound messages to the recorder.
+    pub fn with_recorder(mut self, recorder: Arc<TrafficRecorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Creates serializers that count messages and ping round-trip time.
+    pub fn with_metrics(mut self, metrics: Arc<ConnectorMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+}
 
 #[async_trait]
 impl TcpSerializerFactory<ProtoMessage, ManagerApiSerializer, ManagerApiSerializerState>
     for ManagerApiSerializerFactory
 {
     async fn create_serializer(&self) -> ManagerApiSerializer {
-        ManagerApiSerializer::default()
+        ManagerApiSerializer {
+            recorder: self.recorder.clone(),
+            metrics: self.metrics.clone(),
+        }
     }
 
     async fn create_serializer_state(&self) -> ManagerApiSerializerState {
         ManagerApiSerializerState {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::serialization::{get_frame_len, MAX_FRAME_LEN};
+    use my_tcp_sockets::socket_reader::ReadingTcpContractFail;
+
+    #[test]
+    fn rejects_negative_and_too_big_frame_len() {
+        assert!(matches!(get_frame_len(5i32.to_be_bytes()), Ok(5)));
+        assert!(matches!(
+            get_frame_len((-1i32).to_be_bytes()),
+            Err(ReadingTcpContractFail::ErrorReadingSize)
+        ));
+        assert!(matches!(
+            get_frame_len((MAX_FRAME_LEN as i32 + 1).to_be_bytes()),
+            Err(ReadingTcpContractFail::IncomingPacketTooBig)
+        ));
+    }
+}
@@ -0,0 +1,398 @@
+use crate::manager::common_model_messages_external::ProtoGeoInfo;
+use crate::manager::cs_messages_external::{ProtoTrader, ProtoTraderSessionInfo};
+use crate::manager::event_bus::EventTrader;
+use crate::manager::models::ManagerApiEvent;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+const DEFAULT_MAX_HISTORY_PER_TRADER: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct TraderSession {
+    /// Has no IP address: `ProtoTraderSessionInfo` carries only the geolocation that cServer
+    /// resolves from the IP.
+    pub info: ProtoTraderSessionInfo,
+    /// Time the logon event was received, cServer does not send the logon time.
+    pub logon_time: DateTime<Utc>,
+    pub logout_time: Option<DateTime<Utc>>,
+}
+
+impl TraderSession {
+    pub fn get_country_iso_code(&self) -> Option<&str> {
+        self.get_geo_info()?.country_iso_code.as_deref()
+    }
+
+    pub fn get_city_name(&self) -> Option<&str> {
+        self.get_geo_info()?.city_name.as_deref()
+    }
+
+    fn get_geo_info(&self) -> Option<&ProtoGeoInfo> {
+        self.info.geo_info.as_ref()
+    }
+}
+
+/// Tracks trader sessions from `TraderLogonEvent` and `TraderLogoutEvent`.
+/// Traders are identified by `traderId` which is sent by cServer in the session info.
+/// Session info has no login, so logins are resolved through the login index which is
+/// filled from trader lists (`TraderListRes`) and `TraderChangedEvent`. The index keeps
+/// the group of the trader too.
+/// Note: cServer does not send the IP address of a session, only the geolocation based on it,
+/// so sessions can be matched by country and city, not by IP.
+/// The Manager API has no request for the current sessions, so the online sessions are
+/// cleared when the connection is lost: sessions that end while disconnected would stay online
+/// forever. After a reconnect only the traders that log on again are tracked as online.
+#[derive(Debug, Clone)]
+pub struct TraderSessionRegistry {
+    online_sessions: HashMap<u64, TraderSession>,
+    closed_sessions: HashMap<u64, VecDeque<TraderSession>>,
+    trader_ids_by_login: HashMap<i64, u64>,
+    logins_by_trader_id: HashMap<u64, i64>,
+    group_ids_by_trader_id: HashMap<u64, i64>,
+    max_history_per_trader: usize,
+}
+
+impl Default for TraderSessionRegistry {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_HISTORY_PER_TRADER)
+    }
+}
+
+impl TraderSessionRegistry {
+    /// `max_history_per_trader` limits the count of closed sessions kept for each trader.
+    pub fn new(max_history_per_trader: usize) -> Self {
+        Self {
+            online_sessions: HashMap::new(),
+            closed_sessions: HashMap::new(),
+            trader_ids_by_login: HashMap::new(),
+            logins_by_trader_id: HashMap::new(),
+            group_ids_by_trader_id: HashMap::new(),
+            max_history_per_trader,
+        }
+    }
+
+    /// Applies a logon or logout event. Returns FALSE if the event is not session related.
+    pub fn update(&mut self, event: &ManagerApiEvent) -> bool {
+        self.update_at(event, Utc::now())
+    }
+
+    pub fn update_at(&mut self, event: &ManagerApiEvent, time: DateTime<Utc>) -> bool {
+        match event {
+            ManagerApiEvent::TraderLogonEvent(event) => {
+                let info = event.trade_session_info.clone();
+                self.online_sessions.insert(
+                    info.session_id,
+                    TraderSession {
+                        info,
+                        logon_time: time,
+                        logout_time: None,
+                    },
+                );
+            }
+            ManagerApiEvent::TraderLogoutEvent(event) => {
+                if let Some(mut session) = self.online_sessions.remove(&event.session_id) {
+                    session.logout_time = Some(time);
+                    let history = self
+                        .closed_sessions
+                        .entry(session.info.trader_id)
+                        .or_default();
+                    history.push_back(session);
+
+                    while history.len() > self.max_history_per_trader {
+                        history.pop_front();
+                    }
+                }
+            }
+            ManagerApiEvent::TraderChangedEvent(event) => {
+                self.set_trader(&event.trader);
+                return false;
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Adds the logins and groups of the traders to the login index.
+    pub fn set_traders(&mut self, traders: &[ProtoTrader]) {
+        for trader in traders {
+            self.set_trader(trader);
+        }
+    }
+
+    fn set_trader(&mut self, trader: &ProtoTrader) {
+        self.set_trader_login(trader.trader_id as u64, trader.login);
+        self.group_ids_by_trader_id
+            .insert(trader.trader_id as u64, trader.group_id);
+    }
+
+    pub fn set_trader_login(&mut self, trader_id: u64, login: i64) {
+        if let Some(old_login) = self.logins_by_trader_id.insert(trader_id, login) {
+            self.trader_ids_by_login.remove(&old_login);
+        }
+
+        self.trader_ids_by_login.insert(login, trader_id);
+    }
+
+    /// Returns `None` if the trader is not in the login index.
+    pub fn get_trader_id(&self, login: i64) -> Option<u64> {
+        self.trader_ids_by_login.get(&login).copied()
+    }
+
+    pub fn get_login(&self, trader_id: u64) -> Option<i64> {
+        self.logins_by_trader_id.get(&trader_id).copied()
+    }
+
+    /// Returns `None` if the trader is not in the login index.
+    pub fn get_group_id(&self, trader_id: u64) -> Option<i64> {
+        self.group_ids_by_trader_id.get(&trader_id).copied()
+    }
+
+    /// Resolves the login and group of the trader the event concerns through the login index
+    /// when the event carries only the trader id.
+    pub fn resolve_trader(&self, event: &ManagerApiEvent) -> EventTrader {
+        let trader = EventTrader::from_event(event);
+        let Some(trader_id) = event.get_trader_id() else {
+            return trader;
+        };
+        let trader_id = trader_id as u64;
+
+        EventTrader {
+            login: trader.login.or_else(|| self.get_login(trader_id)),
+            group_id: trader.group_id.or_else(|| self.get_group_id(trader_id)),
+        }
+    }
+
+    /// Drops the online sessions, their logout can not be tracked anymore. The closed
+    /// sessions and the login index are kept.
+    pub fn clear_online_sessions(&mut self) {
+        self.online_sessions.clear();
+    }
+
+    pub fn get_online_sessions(&self) -> impl Iterator<Item = &TraderSession> {
+        self.online_sessions.values()
+    }
+
+    pub fn get_online_trader_ids(&self) -> HashSet<u64> {
+        self.online_sessions
+            .values()
+            .map(|session| session.info.trader_id)
+            .collect()
+    }
+
+    pub fn is_online(&self, trader_id: u64) -> bool {
+        self.online_sessions
+            .values()
+            .any(|session| session.info.trader_id == trader_id)
+    }
+
+    /// Returns FALSE also if the login is not in the login index.
+    pub fn is_login_online(&self, login: i64) -> bool {
+        self.get_trader_id(login)
+            .is_some_and(|trader_id| self.is_online(trader_id))
+    }
+
+    pub fn get_trader_online_sessions(&self, trader_id: u64) -> Vec<&TraderSession> {
+        self.online_sessions
+            .values()
+            .filter(|session| session.info.trader_id == trader_id)
+            .collect()
+    }
+
+    /// Returns closed and online sessions of the trader ordered by logon time.
+    pub fn get_session_history(&self, trader_id: u64) -> Vec<&TraderSession> {
+        let mut sessions: Vec<&TraderSession> = self
+            .closed_sessions
+            .get(&trader_id)
+            .into_iter()
+            .flatten()
+            .chain(self.get_trader_online_sessions(trader_id))
+            .collect();
+        sessions.sort_by_key(|session| session.logon_time);
+
+        sessions
+    }
+
+    /// Returns closed and online sessions of the trader with the login ordered by logon time.
+    /// Returns an empty list if the login is not in the login index.
+    pub fn get_session_history_by_login(&self, login: i64) -> Vec<&TraderSession> {
+        let Some(trader_id) = self.get_trader_id(login) else {
+            return vec![];
+        };
+
+        self.get_session_history(trader_id)
+    }
+
+    /// Returns logins of traders that are online from the specified location.
+    /// Traders that are not in the login index are skipped.
+    pub fn get_logins_by_location(&self, country_iso_code: &str, city_name: &str) -> HashSet<i64> {
+        self.get_traders_by_location(country_iso_code, city_name)
+            .into_iter()
+            .filter_map(|trader_id| self.get_login(trader_id))
+            .collect()
+    }
+
+    /// Returns IDs of traders that are online from the specified location.
+    pub fn get_traders_by_location(&self, country_iso_code: &str, city_name: &str) -> HashSet<u64> {
+        self.online_sessions
+            .values()
+            .filter(|session| {
+                session.get_country_iso_code() == Some(country_iso_code)
+                    && session.get_city_name() == Some(city_name)
+            })
+            .map(|session| session.info.trader_id)
+            .collect()
+    }
+
+    /// Returns online traders grouped by location that have more than one trader,
+    /// which can be a sign of account sharing.
+    pub fn get_shared_locations(&self) -> HashMap<(String, String), HashSet<u64>> {
+        let mut result: HashMap<(String, String), HashSet<u64>> = HashMap::new();
+
+        for session in self.online_sessions.values() {
+            let (Some(country), Some(city)) =
+                (session.get_country_iso_code(), session.get_city_name())
+            else {
+                continue;
+            };
+
+            result
+                .entry((country.to_string(), city.to_string()))
+                .or_default()
+                .insert(session.info.trader_id);
+        }
+
+        result.retain(|_, trader_ids| trader_ids.len() > 1);
+
+        result
+    }
+
+    /// Returns traders that have concurrent online sessions from different countries.
+    pub fn get_concurrent_countries(&self) -> HashMap<u64, HashSet<String>> {
+        let mut result: HashMap<u64, HashSet<String>> = HashMap::new();
+
+        for session in self.online_sessions.values() {
+            if let Some(country) = session.get_country_iso_code() {
+                result
+                    .entry(session.info.trader_id)
+                    .or_default()
+                    .insert(country.to_string());
+            }
+        }
+
+        result.retain(|_, countries| countries.len() > 1);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::common_model_messages_external::ProtoGeoInfo;
+    use crate::manager::cs_messages_external::{
+        ProtoTrader, ProtoTraderLogonEvent, ProtoTraderLogoutEvent, ProtoTraderSessionInfo,
+    };
+    use crate::manager::event_bus::EventTrader;
+    use crate::manager::models::ManagerApiEvent;
+    use crate::manager::sessions::TraderSessionRegistry;
+    use chrono::Utc;
+
+    fn create_logon(session_id: u64, trader_id: u64, country: &str) -> ManagerApiEvent {
+        ManagerApiEvent::TraderLogonEvent(ProtoTraderLogonEvent {
+            payload_type: None,
+            trade_session_info: ProtoTraderSessionInfo {
+                session_id,
+                trader_id,
+                proxy_id: "proxy".to_string(),
+                geo_info: Some(ProtoGeoInfo {
+                    country_iso_code: Some(country.to_string()),
+                    city_name: Some("City".to_string()),
+                }),
+                application_type: None,
+            },
+        })
+    }
+
+    #[test]
+    fn tracks_trader_sessions() {
+        let mut registry = TraderSessionRegistry::default();
+        registry.update_at(&create_logon(1, 10, "CY"), Utc::now());
+        registry.update_at(&create_logon(2, 10, "GB"), Utc::now());
+        registry.update_at(&create_logon(3, 20, "CY"), Utc::now());
+
+        assert!(registry.is_online(10));
+        assert_eq!(registry.get_concurrent_countries()[&10].len(), 2);
+        assert_eq!(
+            registry.get_traders_by_location("CY", "City"),
+            [10, 20].into_iter().collect()
+        );
+
+        registry.update_at(
+            &ManagerApiEvent::TraderLogoutEvent(ProtoTraderLogoutEvent {
+                payload_type: None,
+                session_id: 2,
+                trader_id: Some(10),
+                is_online: Some(true),
+            }),
+            Utc::now(),
+        );
+
+        assert!(registry.get_concurrent_countries().is_empty());
+        let history = registry.get_session_history(10);
+        assert_eq!(history.len(), 2);
+        assert!(history[1].logout_time.is_some());
+
+        registry.clear_online_sessions();
+
+        assert!(!registry.is_online(10));
+        assert!(!registry.is_online(20));
+        assert_eq!(registry.get_session_history(10).len(), 1);
+    }
+
+    #[test]
+    fn resolves_sessions_by_login() {
+        let mut registry = TraderSessionRegistry::default();
+        registry.update_at(&create_logon(1, 10, "CY"), Utc::now());
+        registry.update_at(&create_logon(2, 20, "CY"), Utc::now());
+
+        assert!(!registry.is_login_online(1010));
+        assert!(registry.get_session_history_by_login(1010).is_empty());
+
+        registry.set_trader_login(10, 1010);
+        registry.set_trader_login(20, 1020);
+
+        assert!(registry.is_login_online(1010));
+        assert_eq!(registry.get_session_history_by_login(1020).len(), 1);
+        assert_eq!(
+            registry.get_logins_by_location("CY", "City"),
+            [1010, 1020].into_iter().collect()
+        );
+
+        registry.set_trader_login(10, 1011);
+
+        assert_eq!(registry.get_trader_id(1010), None);
+        assert_eq!(registry.get_trader_id(1011), Some(10));
+    }
+
+    #[test]
+    fn resolves_event_trader_through_login_index() {
+        let mut registry = TraderSessionRegistry::default();
+        let logon = create_logon(1, 10, "CY");
+
+        assert_eq!(registry.resolve_trader(&logon), EventTrader::default());
+
+        registry.set_traders(&[ProtoTrader {
+            trader_id: 10,
+            login: 1010,
+            group_id: 5,
+            ..Default::default()
+        }]);
+
+        assert_eq!(
+            registry.resolve_trader(&logon),
+            EventTrader {
+                login: Some(1010),
+                group_id: Some(5),
+            }
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use crate::manager::cs_messages_external::ProtoCsPayloadType;
+use std::time::Duration;
+
+/// Sent request without a response from the server.
+#[derive(Debug, Clone)]
+pub struct UnconfirmedRequest {
+    pub client_msg_id: String,
+    pub payload_type: ProtoCsPayloadType,
+    /// Time since the request was sent.
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ManagerApiShutdownReport {
+    /// Requests that were sent but not confirmed before the deadline. Their outcome is
+    /// unknown: they may have been executed by the server.
+    pub unconfirmed_requests: Vec<UnconfirmedRequest>,
+    /// Count of requests which were still waiting for the connection at the deadline. They are
+    /// listed in `unconfirmed_requests` too. Requests waiting for the rate limiter fail when
+    /// the shutdown starts.
+    pub queued_requests: usize,
+    /// True when the client was disconnected before all requests were confirmed.
+    pub is_disconnected_early: bool,
+}
+
+impl ManagerApiShutdownReport {
+    pub fn is_clean(&self) -> bool {
+        self.unconfirmed_requests.is_empty() && self.queued_requests == 0
+    }
+}
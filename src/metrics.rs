@@ -0,0 +1,340 @@
+use crate::manager::common_model_messages_external::ProtoPayloadType;
+use crate::manager::cs_messages_external::ProtoCsPayloadType;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Upper bounds of the latency histogram buckets in seconds.
+pub const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LatencyHistogram {
+    /// Count of observations per bucket of `LATENCY_BUCKETS` (not cumulative).
+    /// The last item counts observations above the last bucket.
+    pub bucket_counts: Vec<u64>,
+    pub sum_seconds: f64,
+    pub count: u64,
+}
+
+impl LatencyHistogram {
+    fn observe(&mut self, latency: Duration) {
+        if self.bucket_counts.is_empty() {
+            self.bucket_counts = vec![0; LATENCY_BUCKETS.len() + 1];
+        }
+
+        let seconds = latency.as_secs_f64();
+        let index = LATENCY_BUCKETS
+            .iter()
+            .position(|bucket| seconds <= *bucket)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.bucket_counts[index] += 1;
+        self.sum_seconds += seconds;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+struct MetricsState {
+    received_messages: HashMap<u32, u64>,
+    sent_messages: HashMap<u32, u64>,
+    decode_failures: u64,
+    handler_dropped_messages: u64,
+    connects: u64,
+    disconnected_at: Option<Instant>,
+    downtime: Duration,
+    last_ping_rtt: Option<Duration>,
+    http_latencies: HashMap<&'static str, LatencyHistogram>,
+    http_statuses: HashMap<(&'static str, Option<u16>), u64>,
+}
+
+/// Counters of a Manager API connection and Webservices requests.
+#[derive(Default)]
+pub struct ConnectorMetrics {
+    state: Mutex<MetricsState>,
+}
+
+impl ConnectorMetrics {
+    pub fn on_message_received(&self, payload_type: u32) {
+        *self
+            .state
+            .lock()
+            .unwrap()
+            .received_messages
+            .entry(payload_type)
+            .or_default() += 1;
+    }
+
+    pub fn on_message_sent(&self, payload_type: u32) {
+        *self
+            .state
+            .lock()
+            .unwrap()
+            .sent_messages
+            .entry(payload_type)
+            .or_default() += 1;
+    }
+
+    pub fn on_decode_failure(&self) {
+        self.state.lock().unwrap().decode_failures += 1;
+    }
+
+    /// Called when the full handler queue drops messages.
+    pub fn on_handler_messages_dropped(&self, count: usize) {
+        self.state.lock().unwrap().handler_dropped_messages += count as u64;
+    }
+
+    pub fn on_connected(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.connects += 1;
+
+        if let Some(disconnected_at) = state.disconnected_at.take() {
+            state.downtime += disconnected_at.elapsed();
+        }
+    }
+
+    pub fn on_disconnected(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        if state.disconnected_at.is_none() {
+            state.disconnected_at = Some(Instant::now());
+        }
+    }
+
+    pub fn on_ping_rtt(&self, rtt: Duration) {
+        self.state.lock().unwrap().last_ping_rtt = Some(rtt);
+    }
+
+    /// `status_code` is `None` when no response was received.
+    pub fn on_http_response(
+        &self,
+        endpoint: &'static str,
+        status_code: Option<u16>,
+        latency: Duration,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .http_latencies
+            .entry(endpoint)
+            .or_default()
+            .observe(latency);
+        *state
+            .http_statuses
+            .entry((endpoint, status_code))
+            .or_default() += 1;
+    }
+
+    pub fn get_snapshot(&self) -> MetricsSnapshot {
+        let state = self.state.lock().unwrap();
+        let current_downtime = state
+            .disconnected_at
+            .map(|disconnected_at| disconnected_at.elapsed())
+            .unwrap_or_default();
+
+        MetricsSnapshot {
+            received_messages: get_named_counts(&state.received_messages),
+            sent_messages: get_named_counts(&state.sent_messages),
+            decode_failures: state.decode_failures,
+            handler_dropped_messages: state.handler_dropped_messages,
+            connects: state.connects,
+            reconnects: state.connects.saturating_sub(1),
+            is_connected: state.connects > 0 && state.disconnected_at.is_none(),
+            downtime: state.downtime + current_downtime,
+            last_ping_rtt: state.last_ping_rtt,
+            http_latencies: state
+                .http_latencies
+                .iter()
+                .map(|(endpoint, histogram)| (endpoint.to_string(), histogram.clone()))
+                .collect(),
+            http_statuses: state
+                .http_statuses
+                .iter()
+                .map(|((endpoint, status_code), count)| {
+                    let status = status_code
+                        .map(|code| code.to_string())
+                        .unwrap_or_else(|| "none".to_string());
+                    ((endpoint.to_string(), status), *count)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MetricsSnapshot {
+    /// Count of received messages by payload type name.
+    pub received_messages: BTreeMap<String, u64>,
+    /// Count of sent messages by payload type name.
+    pub sent_messages: BTreeMap<String, u64>,
+    pub decode_failures: u64,
+    /// Count of messages dropped by the full handler queue.
+    pub handler_dropped_messages: u64,
+    pub connects: u64,
+    pub reconnects: u64,
+    pub is_connected: bool,
+    /// Total time without connection after the first connect.
+    pub downtime: Duration,
+    pub last_ping_rtt: Option<Duration>,
+    /// Request latency by Webservices endpoint name.
+    pub http_latencies: BTreeMap<String, LatencyHistogram>,
+    /// Count of responses by Webservices endpoint name and HTTP status ("none" without response).
+    pub http_statuses: BTreeMap<(String, String), u64>,
+}
+
+impl MetricsSnapshot {
+    /// Formats the snapshot in Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        write_header(&mut out, "ctrader_messages_received_total", "counter");
+        for (payload_type, count) in self.received_messages.iter() {
+            let _ = writeln!(
+                out,
+                "ctrader_messages_received_total{{payload_type=\"{payload_type}\"}} {count}"
+            );
+        }
+
+        write_header(&mut out, "ctrader_messages_sent_total", "counter");
+        for (payload_type, count) in self.sent_messages.iter() {
+            let _ = writeln!(
+                out,
+                "ctrader_messages_sent_total{{payload_type=\"{payload_type}\"}} {count}"
+            );
+        }
+
+        write_header(&mut out, "ctrader_decode_failures_total", "counter");
+        let _ = writeln!(
+            out,
+            "ctrader_decode_failures_total {}",
+            self.decode_failures
+        );
+        write_header(
+            &mut out,
+            "ctrader_handler_dropped_messages_total",
+            "counter",
+        );
+        let _ = writeln!(
+            out,
+            "ctrader_handler_dropped_messages_total {}",
+            self.handler_dropped_messages
+        );
+        write_header(&mut out, "ctrader_reconnects_total", "counter");
+        let _ = writeln!(out, "ctrader_reconnects_total {}", self.reconnects);
+        write_header(&mut out, "ctrader_connected", "gauge");
+        let _ = writeln!(out, "ctrader_connected {}", self.is_connected as u8);
+        write_header(&mut out, "ctrader_downtime_seconds_total", "counter");
+        let _ = writeln!(
+            out,
+            "ctrader_downtime_seconds_total {}",
+            self.downtime.as_secs_f64()
+        );
+
+        if let Some(rtt) = self.last_ping_rtt {
+            write_header(&mut out, "ctrader_ping_rtt_seconds", "gauge");
+            let _ = writeln!(out, "ctrader_ping_rtt_seconds {}", rtt.as_secs_f64());
+        }
+
+        write_header(
+            &mut out,
+            "ctrader_http_request_duration_seconds",
+            "histogram",
+        );
+        for (endpoint, histogram) in self.http_latencies.iter() {
+            let mut cumulative_count = 0;
+
+            for (index, bucket) in LATENCY_BUCKETS.iter().enumerate() {
+                cumulative_count += histogram.bucket_counts.get(index).copied().unwrap_or(0);
+                let _ = writeln!(
+                    out,
+                    "ctrader_http_request_duration_seconds_bucket{{endpoint=\"{endpoint}\",le=\"{bucket}\"}} {cumulative_count}"
+                );
+            }
+
+            let _ = writeln!(
+                out,
+                "ctrader_http_request_duration_seconds_bucket{{endpoint=\"{endpoint}\",le=\"+Inf\"}} {}",
+                histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "ctrader_http_request_duration_seconds_sum{{endpoint=\"{endpoint}\"}} {}",
+                histogram.sum_seconds
+            );
+            let _ = writeln!(
+                out,
+                "ctrader_http_request_duration_seconds_count{{endpoint=\"{endpoint}\"}} {}",
+                histogram.count
+            );
+        }
+
+        write_header(&mut out, "ctrader_http_responses_total", "counter");
+        for ((endpoint, status), count) in self.http_statuses.iter() {
+            let _ = writeln!(
+                out,
+                "ctrader_http_responses_total{{endpoint=\"{endpoint}\",status=\"{status}\"}} {count}"
+            );
+        }
+
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, metric_type: &str) {
+    let _ = writeln!(out, "# TYPE {name} {metric_type}");
+}
+
+fn get_named_counts(counts: &HashMap<u32, u64>) -> BTreeMap<String, u64> {
+    counts
+        .iter()
+        .map(|(payload_type, count)| (get_payload_type_name(*payload_type), *count))
+        .collect()
+}
+
+fn get_payload_type_name(payload_type: u32) -> String {
+    if let Ok(payload_type) = ProtoCsPayloadType::try_from(payload_type as i32) {
+        return payload_type.as_str_name().to_string();
+    }
+
+    if let Ok(payload_type) = ProtoPayloadType::try_from(payload_type as i32) {
+        return payload_type.as_str_name().to_string();
+    }
+
+    payload_type.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::cs_messages_external::ProtoCsPayloadType;
+    use crate::metrics::ConnectorMetrics;
+    use std::time::Duration;
+
+    #[test]
+    fn exports_metrics_snapshot() {
+        let metrics = ConnectorMetrics::default();
+        metrics.on_connected();
+        metrics.on_disconnected();
+        metrics.on_connected();
+        metrics.on_message_received(ProtoCsPayloadType::ProtoExecutionEvent as u32);
+        metrics.on_http_response("GetTrader", Some(200), Duration::from_millis(30));
+        metrics.on_http_response("GetTrader", None, Duration::from_secs(20));
+
+        let snapshot = metrics.get_snapshot();
+        assert_eq!(snapshot.reconnects, 1);
+        assert!(snapshot.is_connected);
+        assert_eq!(snapshot.received_messages["PROTO_EXECUTION_EVENT"], 1);
+        assert_eq!(snapshot.http_latencies["GetTrader"].count, 2);
+
+        let text = snapshot.to_prometheus();
+        assert!(text.contains(
+            "ctrader_http_request_duration_seconds_bucket{endpoint=\"GetTrader\",le=\"0.05\"} 1"
+        ));
+        assert!(text.contains(
+            "ctrader_http_request_duration_seconds_bucket{endpoint=\"GetTrader\",le=\"+Inf\"} 2"
+        ));
+        assert!(
+            text.contains("ctrader_http_responses_total{endpoint=\"GetTrader\",status=\"none\"} 1")
+        );
+    }
+}
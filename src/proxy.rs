@@ -0,0 +1,343 @@
+use crate::manager::transport::format_host_port;
+use base64::Engine;
+use std::fmt::{Debug, Formatter};
+use std::net::IpAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const MAX_CONNECT_RESPONSE_SIZE: usize = 8 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyKind {
+    /// HTTP proxy that supports the CONNECT method.
+    HttpConnect,
+    Socks5,
+}
+
+/// Outbound proxy which the connections are tunneled through.
+#[derive(Debug, Clone)]
+pub struct ProxyConfig {
+    pub kind: ProxyKind,
+    /// Address of the proxy in the `host:port` format.
+    pub address: String,
+    pub credentials: Option<ProxyCredentials>,
+}
+
+#[derive(Clone)]
+pub struct ProxyCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Debug for ProxyCredentials {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProxyCredentials")
+            .field("username", &self.username)
+            .field("password", &"***")
+            .finish()
+    }
+}
+
+impl ProxyConfig {
+    pub fn http(address: impl Into<String>) -> Self {
+        Self {
+            kind: ProxyKind::HttpConnect,
+            address: address.into(),
+            credentials: None,
+        }
+    }
+
+    pub fn socks5(address: impl Into<String>) -> Self {
+        Self {
+            kind: ProxyKind::Socks5,
+            address: address.into(),
+            credentials: None,
+        }
+    }
+
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some(ProxyCredentials {
+            username: username.into(),
+            password: password.into(),
+        });
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.address.trim().is_empty() || !self.address.contains(':') {
+            return Err(format!(
+                "Invalid proxy address {}: expected host:port",
+                self.address
+            ));
+        }
+
+        if let (ProxyKind::Socks5, Some(credentials)) = (self.kind, &self.credentials) {
+            if credentials.username.len() > 255 || credentials.password.len() > 255 {
+                return Err("SOCKS5 username and password must not exceed 255 bytes".to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Connects to the proxy and opens a tunnel to the target `host:port`.
+    pub async fn connect(&self, target_host: &str, target_port: u16) -> Result<TcpStream, String> {
+        let mut stream = TcpStream::connect(&self.address)
+            .await
+            .map_err(|e| format!("Failed to connect to proxy {}: {}", self.address, e))?;
+
+        match self.kind {
+            ProxyKind::HttpConnect => {
+                self.open_http_tunnel(&mut stream, target_host, target_port)
+                    .await?
+            }
+            ProxyKind::Socks5 => {
+                self.open_socks5_tunnel(&mut stream, target_host, target_port)
+                    .await?
+            }
+        }
+
+        Ok(stream)
+    }
+
+    async fn open_http_tunnel(
+        &self,
+        stream: &mut TcpStream,
+        target_host: &str,
+        target_port: u16,
+    ) -> Result<(), String> {
+        let target = format_host_port(target_host, target_port);
+        let mut request = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n");
+
+        if let Some(credentials) = &self.credentials {
+            let auth = base64::engine::general_purpose::STANDARD
+                .encode(format!("{}:{}", credentials.username, credentials.password));
+            request.push_str(&format!("Proxy-Authorization: Basic {auth}\r\n"));
+        }
+
+        request.push_str("\r\n");
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to send CONNECT to proxy: {}", e))?;
+
+        // read byte by byte to not consume the data of the tunnel
+        let mut response = Vec::new();
+
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() > MAX_CONNECT_RESPONSE_SIZE {
+                return Err("Proxy CONNECT response is too big".to_string());
+            }
+
+            let byte = stream
+                .read_u8()
+                .await
+                .map_err(|e| format!("Failed to read CONNECT response from proxy: {}", e))?;
+            response.push(byte);
+        }
+
+        let response = String::from_utf8_lossy(&response);
+        let status_line = response.lines().next().unwrap_or_default();
+        let status_code = status_line.split_whitespace().nth(1);
+
+        if status_code != Some("200") {
+            return Err(format!("Proxy refused CONNECT: {}", status_line));
+        }
+
+        Ok(())
+    }
+
+    async fn open_socks5_tunnel(
+        &self,
+        stream: &mut TcpStream,
+        target_host: &str,
+        target_port: u16,
+    ) -> Result<(), String> {
+        let map_err = |e: std::io::Error| format!("SOCKS5 handshake failed: {}", e);
+        let greeting: &[u8] = if self.credentials.is_some() {
+            &[5, 2, 0, 2]
+        } else {
+            &[5, 1, 0]
+        };
+        stream.write_all(greeting).await.map_err(map_err)?;
+
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply).await.map_err(map_err)?;
+
+        match (reply[1], &self.credentials) {
+            (0, _) => {}
+            (2, Some(credentials)) => {
+                let mut auth = vec![1, credentials.username.len() as u8];
+                auth.extend_from_slice(credentials.username.as_bytes());
+                auth.push(credentials.password.len() as u8);
+                auth.extend_from_slice(credentials.password.as_bytes());
+                stream.write_all(&auth).await.map_err(map_err)?;
+
+                stream.read_exact(&mut reply).await.map_err(map_err)?;
+
+                if reply[1] != 0 {
+                    return Err("SOCKS5 proxy rejected the credentials".to_string());
+                }
+            }
+            _ => return Err("SOCKS5 proxy does not support the auth method".to_string()),
+        }
+
+        let mut request = vec![5, 1, 0];
+
+        match target_host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                request.push(1);
+                request.extend_from_slice(&ip.octets());
+            }
+            Ok(IpAddr::V6(ip)) => {
+                request.push(4);
+                request.extend_from_slice(&ip.octets());
+            }
+            Err(_) => {
+                if target_host.len() > 255 {
+                    return Err(format!("SOCKS5 host is too long: {}", target_host));
+                }
+
+                request.push(3);
+                request.push(target_host.len() as u8);
+                request.extend_from_slice(target_host.as_bytes());
+            }
+        }
+
+        request.extend_from_slice(&target_port.to_be_bytes());
+        stream.write_all(&request).await.map_err(map_err)?;
+
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).await.map_err(map_err)?;
+
+        if reply[1] != 0 {
+            return Err(format!(
+                "SOCKS5 proxy refused to connect: code {}",
+                reply[1]
+            ));
+        }
+
+        // skip the bound address and port
+        let address_size = match reply[3] {
+            1 => 4,
+            4 => 16,
+            3 => stream.read_u8().await.map_err(map_err)? as usize,
+            atyp => {
+                return Err(format!(
+                    "SOCKS5 proxy returned unknown address type {}",
+                    atyp
+                ))
+            }
+        };
+        let mut bound_address = vec![0u8; address_size + 2];
+        stream
+            .read_exact(&mut bound_address)
+            .await
+            .map_err(map_err)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::proxy::ProxyConfig;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    async fn start_echo_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let (mut reader, mut writer) = socket.split();
+                    let _ = tokio::io::copy(&mut reader, &mut writer).await;
+                });
+            }
+        });
+
+        port
+    }
+
+    async fn assert_echo(mut stream: TcpStream) {
+        stream.write_all(b"ping").await.unwrap();
+        let mut buffer = [0u8; 4];
+        stream.read_exact(&mut buffer).await.unwrap();
+
+        assert_eq!(&buffer, b"ping");
+    }
+
+    #[tokio::test]
+    async fn connects_via_http_proxy() {
+        let echo_port = start_echo_server().await;
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_address = proxy.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut socket, _) = proxy.accept().await.unwrap();
+            let mut request = Vec::new();
+
+            while !request.ends_with(b"\r\n\r\n") {
+                request.push(socket.read_u8().await.unwrap());
+            }
+
+            let request = String::from_utf8(request).unwrap();
+            assert!(request.starts_with(&format!("CONNECT 127.0.0.1:{} HTTP/1.1", echo_port)));
+            // base64 of user:pass
+            assert!(request.contains("Proxy-Authorization: Basic dXNlcjpwYXNz"));
+
+            let mut upstream = TcpStream::connect(("127.0.0.1", echo_port)).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                .await
+                .unwrap();
+            let _ = tokio::io::copy_bidirectional(&mut socket, &mut upstream).await;
+        });
+
+        let stream = ProxyConfig::http(proxy_address)
+            .with_credentials("user", "pass")
+            .connect("127.0.0.1", echo_port)
+            .await
+            .unwrap();
+
+        assert_echo(stream).await;
+    }
+
+    #[tokio::test]
+    async fn connects_via_socks5_proxy() {
+        let echo_port = start_echo_server().await;
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_address = proxy.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut socket, _) = proxy.accept().await.unwrap();
+            let mut greeting = [0u8; 3];
+            socket.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [5, 1, 0]);
+            socket.write_all(&[5, 0]).await.unwrap();
+
+            let mut request = [0u8; 10];
+            socket.read_exact(&mut request).await.unwrap();
+            assert_eq!(request[..8], [5, 1, 0, 1, 127, 0, 0, 1]);
+            let port = u16::from_be_bytes([request[8], request[9]]);
+
+            let mut upstream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+            socket
+                .write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 0])
+                .await
+                .unwrap();
+            let _ = tokio::io::copy_bidirectional(&mut socket, &mut upstream).await;
+        });
+
+        let stream = ProxyConfig::socks5(proxy_address)
+            .connect("127.0.0.1", echo_port)
+            .await
+            .unwrap();
+
+        assert_echo(stream).await;
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Bucket of the Webservices requests.
+pub const WEBSERVICES_GROUP: &str = "webservices";
+/// Bucket of the Manager API requests.
+pub const MANAGER_API_GROUP: &str = "manager_api";
+
+/// While a token is awaited by a request with a higher priority the others wait for this time.
+const BLOCKED_BY_PRIORITY_DELAY: Duration = Duration::from_millis(5);
+
+/// Order in which the waiting requests get the tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RequestPriority {
+    /// Risk actions, e.g. closing positions or changing access rights.
+    High = 0,
+    Normal = 1,
+    /// Bulk reads, e.g. history.
+    Low = 2,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TokenBucketSettings {
+    /// Max count of requests that can be sent at once.
+    pub capacity: u32,
+    pub refill_per_second: f64,
+}
+
+impl TokenBucketSettings {
+    /// An empty bucket or a bucket without refill would block the requests forever.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.capacity == 0 {
+            return Err("Token bucket capacity must be greater than 0".to_string());
+        }
+
+        if !self.refill_per_second.is_finite() || self.refill_per_second <= 0.0 {
+            return Err(format!(
+                "Token bucket refill_per_second must be a positive number: {}",
+                self.refill_per_second
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueDepth {
+    pub high: usize,
+    pub normal: usize,
+    pub low: usize,
+}
+
+impl QueueDepth {
+    pub fn get_total(&self) -> usize {
+        self.high + self.normal + self.low
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    settings: TokenBucketSettings,
+    tokens: f64,
+    refilled_at: Instant,
+    waiting: [usize; 3],
+}
+
+impl TokenBucket {
+    fn new(settings: TokenBucketSettings) -> Self {
+        Self {
+            settings,
+            tokens: settings.capacity as f64,
+            refilled_at: Instant::now(),
+            waiting: [0; 3],
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.settings.refill_per_second)
+            .min(self.settings.capacity as f64);
+        self.refilled_at = now;
+    }
+
+    /// Takes a token or returns the time to wait before the next try.
+    fn try_take(&mut self, priority: RequestPriority) -> Result<(), Duration> {
+        self.refill();
+        let is_blocked = self.waiting[..priority as usize]
+            .iter()
+            .any(|waiting| *waiting > 0);
+
+        if is_blocked {
+            return Err(BLOCKED_BY_PRIORITY_DELAY);
+        }
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+
+        Err(Duration::from_secs_f64(
+            (1.0 - self.tokens) / self.settings.refill_per_second,
+        ))
+    }
+}
+
+/// Token bucket per endpoint group. Can be shared by several clients, so they do not exceed
+/// the limits of the broker together. Requests of the groups without a bucket are not limited.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    buckets: HashMap<String, Mutex<TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// Returns an error if the settings are invalid.
+    pub fn with_group(
+        mut self,
+        group: impl Into<String>,
+        settings: TokenBucketSettings,
+    ) -> Result<Self, String> {
+        settings.validate()?;
+        self.buckets
+            .insert(group.into(), Mutex::new(TokenBucket::new(settings)));
+
+        Ok(self)
+    }
+
+    /// Waits until the request of the group is allowed to be sent.
+    pub async fn acquire(&self, group: &str, priority: RequestPriority) {
+        let Some(bucket) = self.buckets.get(group) else {
+            return;
+        };
+
+        let _waiting = WaitingGuard::new(bucket, priority);
+
+        loop {
+            let result = bucket.lock().unwrap().try_take(priority);
+
+            match result {
+                Ok(()) => return,
+                Err(delay) => tokio::time::sleep(delay).await,
+            }
+        }
+    }
+
+    /// Count of the requests waiting for a token. `None` if the group has no bucket.
+    pub fn get_queue_depth(&self, group: &str) -> Option<QueueDepth> {
+        let bucket = self.buckets.get(group)?.lock().unwrap();
+
+        Some(QueueDepth {
+            high: bucket.waiting[RequestPriority::High as usize],
+            normal: bucket.waiting[RequestPriority::Normal as usize],
+            low: bucket.waiting[RequestPriority::Low as usize],
+        })
+    }
+
+    pub fn get_queue_depths(&self) -> HashMap<String, QueueDepth> {
+        self.buckets
+            .keys()
+            .filter_map(|group| Some((group.clone(), self.get_queue_depth(group)?)))
+            .collect()
+    }
+}
+
+/// Counts the request as waiting until it gets a token or is cancelled.
+struct WaitingGuard<'a> {
+    bucket: &'a Mutex<TokenBucket>,
+    priority: RequestPriority,
+}
+
+impl<'a> WaitingGuard<'a> {
+    fn new(bucket: &'a Mutex<TokenBucket>, priority: RequestPriority) -> Self {
+        bucket.lock().unwrap().waiting[priority as usize] += 1;
+
+        Self { bucket, priority }
+    }
+}
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.bucket.lock().unwrap().waiting[self.priority as usize] -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rate_limiter::{RateLimiter, RequestPriority, TokenBucketSettings};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::Mutex;
+
+    #[tokio::test]
+    async fn serves_high_priority_first() {
+        let limiter = Arc::new(
+            RateLimiter::default()
+                .with_group(
+                    "test",
+                    TokenBucketSettings {
+                        capacity: 1,
+                        refill_per_second: 20.0,
+                    },
+                )
+                .unwrap(),
+        );
+        limiter.acquire("test", RequestPriority::Normal).await;
+        // not limited group
+        limiter.acquire("other", RequestPriority::Low).await;
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut handles = Vec::new();
+
+        for priority in [
+            RequestPriority::Low,
+            RequestPriority::Low,
+            RequestPriority::High,
+        ] {
+            let limiter = Arc::clone(&limiter);
+            let order = Arc::clone(&order);
+            handles.push(tokio::spawn(async move {
+                limiter.acquire("test", priority).await;
+                order.lock().await.push(priority);
+            }));
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let depth = limiter.get_queue_depth("test").unwrap();
+        assert_eq!(depth.get_total(), 3);
+        assert!(limiter.get_queue_depth("other").is_none());
+
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(order.lock().await[0], RequestPriority::High);
+        assert_eq!(limiter.get_queue_depth("test").unwrap().get_total(), 0);
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        for (capacity, refill_per_second) in [(0, 1.0), (1, 0.0), (1, f64::NAN), (1, f64::INFINITY)]
+        {
+            let settings = TokenBucketSettings {
+                capacity,
+                refill_per_second,
+            };
+
+            assert!(RateLimiter::default().with_group("test", settings).is_err());
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Masks secrets in error and debug strings produced by the crate.
+
+use std::sync::RwLock;
+
+const MASK: &str = "***";
+
+/// Field names which values are always masked.
+pub const DEFAULT_REDACTED_FIELDS: [&str; 9] = [
+    "token",
+    "hashedPassword",
+    "hashed_password",
+    "traderPasswordHash",
+    "trader_password_hash",
+    "passwordHash",
+    "password_hash",
+    "password",
+    "accessToken",
+];
+
+static EXTRA_REDACTED_FIELDS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Adds field names which values must be masked in addition to `DEFAULT_REDACTED_FIELDS`.
+pub fn add_redacted_fields<S: Into<String>>(fields: impl IntoIterator<Item = S>) {
+    let mut extra_fields = EXTRA_REDACTED_FIELDS.write().unwrap();
+
+    for field in fields {
+        let field = field.into();
+
+        if !field.is_empty() && !extra_fields.contains(&field) {
+            extra_fields.push(field);
+        }
+    }
+}
+
+/// Masks values of the redacted fields in query strings (`token=...`), JSON (`"token":"..."`),
+/// escaped JSON and Debug output of structs (`hashed_password: "..."`, `token: Some("...")`).
+pub fn redact(text: &str) -> String {
+    let mut result = text.to_string();
+
+    for field in DEFAULT_REDACTED_FIELDS {
+        result = redact_field(&result, field);
+    }
+
+    for field in EXTRA_REDACTED_FIELDS.read().unwrap().iter() {
+        result = redact_field(&result, field);
+    }
+
+    result
+}
+
+fn redact_field(text: &str, field: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut result = String::with_capacity(text.len());
+    let mut copied_to = 0;
+    let mut search_from = 0;
+
+    while let Some(found) = text[search_from..].find(field) {
+        let name_start = search_from + found;
+        let name_end = name_start + field.len();
+        search_from = name_end;
+
+        if name_start > 0 && is_identifier_byte(bytes[name_start - 1]) {
+            continue;
+        }
+
+        let Some((value_start, value_end)) = find_value(bytes, name_end) else {
+            continue;
+        };
+
+        result.push_str(&text[copied_to..value_start]);
+        result.push_str(MASK);
+        copied_to = value_end;
+        search_from = value_end;
+    }
+
+    result.push_str(&text[copied_to..]);
+
+    result
+}
+
+/// Returns the range of the value that follows the field name at `position`.
+fn find_value(bytes: &[u8], mut position: usize) -> Option<(usize, usize)> {
+    while position < bytes.len() && matches!(bytes[position], b'"' | b'\\') {
+        position += 1;
+    }
+
+    if position >= bytes.len() || !matches!(bytes[position], b'=' | b':') {
+        return None;
+    }
+
+    position += 1;
+
+    while position < bytes.len() && bytes[position] == b' ' {
+        position += 1;
+    }
+
+    if bytes[position..].starts_with(b"Some(") {
+        position += "Some(".len();
+    }
+
+    while position < bytes.len() && bytes[position] == b'\\' {
+        position += 1;
+    }
+
+    let is_quoted = position < bytes.len() && bytes[position] == b'"';
+
+    if is_quoted {
+        position += 1;
+    }
+
+    let value_start = position;
+
+    while position < bytes.len() {
+        let byte = bytes[position];
+        let is_end = if is_quoted {
+            matches!(byte, b'"' | b'\\')
+        } else {
+            byte.is_ascii_whitespace()
+                || matches!(byte, b'&' | b',' | b'}' | b')' | b']' | b';' | b'"' | b'\\')
+        };
+
+        if is_end {
+            break;
+        }
+
+        position += 1;
+    }
+
+    if position == value_start {
+        return None;
+    }
+
+    Some((value_start, position))
+}
+
+fn is_identifier_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::redaction::{add_redacted_fields, redact};
+
+    #[test]
+    fn redacts_secrets() {
+        assert_eq!(
+            redact("POST https://host/v2/webserv/traders?token=abc-123&login=1"),
+            "POST https://host/v2/webserv/traders?token=***&login=1"
+        );
+        assert_eq!(
+            redact(r#"{"login":1,"hashedPassword": "5f4dcc3b"}"#),
+            r#"{"login":1,"hashedPassword": "***"}"#
+        );
+        assert_eq!(
+            redact(r#"Request: Some("{\"traderPasswordHash\":\"5f4dcc3b\"}")"#),
+            r#"Request: Some("{\"traderPasswordHash\":\"***\"}")"#
+        );
+        assert_eq!(
+            redact(r#"Trader { login: 1, password_hash: Some("5f4dcc3b") }"#),
+            r#"Trader { login: 1, password_hash: Some("***") }"#
+        );
+        assert_eq!(redact("tokens: 5, my_token=1"), "tokens: 5, my_token=1");
+
+        add_redacted_fields(["email"]);
+        assert_eq!(redact(r#"{"email":"a@b.c"}"#), r#"{"email":"***"}"#);
+    }
+}
@@ -0,0 +1,97 @@
+//! Structured spans for Webservices calls and Manager API requests, enabled by the `tracing`
+//! feature. Spans never carry URLs with query strings or request bodies, so the `token`
+//! parameter and password hashes are not exposed. Without the feature all calls are no-op.
+
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+pub(crate) struct RequestSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "tracing")]
+    started: Instant,
+}
+
+impl RequestSpan {
+    #[cfg(feature = "tracing")]
+    pub fn webservices(endpoint: &crate::webservices::endpoints::WebservicesApiEndpoint) -> Self {
+        let span = tracing::info_span!(
+            "webservices_request",
+            endpoint = endpoint.get_name(),
+            method = %endpoint.get_http_method(),
+            path = %String::from(endpoint),
+            login = endpoint.get_login(),
+            status = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+        );
+
+        Self {
+            span,
+            started: Instant::now(),
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub fn webservices(_endpoint: &crate::webservices::endpoints::WebservicesApiEndpoint) -> Self {
+        Self {}
+    }
+
+    #[cfg(feature = "tracing")]
+    pub fn manager(
+        payload_type: crate::manager::cs_messages_external::ProtoCsPayloadType,
+        client_msg_id: &str,
+        login: i64,
+    ) -> Self {
+        let span = tracing::info_span!(
+            "manager_api_request",
+            endpoint = payload_type.as_str_name(),
+            client_msg_id,
+            login,
+            status = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+        );
+
+        Self {
+            span,
+            started: Instant::now(),
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub fn manager(
+        _payload_type: crate::manager::cs_messages_external::ProtoCsPayloadType,
+        _client_msg_id: &str,
+        _login: i64,
+    ) -> Self {
+        Self {}
+    }
+
+    /// Runs the future inside the span, so events emitted while it is polled (e.g. by the
+    /// HTTP client or the socket) belong to the request.
+    #[cfg(feature = "tracing")]
+    pub fn instrument<F: std::future::Future>(
+        &self,
+        future: F,
+    ) -> tracing::instrument::Instrumented<F> {
+        tracing::Instrument::instrument(future, self.span.clone())
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub fn instrument<F: std::future::Future>(&self, future: F) -> F {
+        future
+    }
+
+    /// Records the status and latency and closes the span.
+    #[cfg(feature = "tracing")]
+    pub fn finish(self, status: &str) {
+        let latency_ms = self.started.elapsed().as_millis() as u64;
+        self.span.record("status", status);
+        self.span.record("latency_ms", latency_ms);
+        self.span.in_scope(|| {
+            tracing::debug!(status, latency_ms, "request finished");
+        });
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub fn finish(self, _status: &str) {}
+}
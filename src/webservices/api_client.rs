@@ -1,4 +1,13 @@
+use crate::manager::relay::{LocalRelay, RelayTarget, RelayTls};
+use crate::manager::transport::{
+    format_host_port, parse_host_port, parse_server_name, ManagerApiTlsConfig,
+};
+use crate::metrics::ConnectorMetrics;
 use crate::models::ManagerCreds;
+use crate::proxy::ProxyConfig;
+use crate::rate_limiter::{RateLimiter, WEBSERVICES_GROUP};
+use crate::redaction::redact;
+use crate::telemetry::RequestSpan;
 use crate::utils::generate_password_hash;
 use crate::webservices::endpoints::WebservicesApiEndpoint;
 use crate::webservices::errors::Error;
@@ -6,6 +15,8 @@ use crate::webservices::models::{
     CreateCtidRequest, CreateCtidResponse, CreateCtraderManagerTokenRequest,
     CreateCtraderManagerTokenResponse, CreateTraderRequest,
 };
+use crate::webservices::retry::{BalanceChangeVerifier, FailureKind, RetryPolicy};
+use crate::webservices::shutdown::{UnconfirmedCall, WebservicesShutdownReport};
 use crate::webservices::{
     ClosedPositionModel, CreateTraderResponse, GetClosedPositionsRequest,
     GetOpenedPositionsRequest, GetSymbolsResponse, GetTraderGroupsResponse, GetTradersRequest,
@@ -13,14 +24,20 @@ use crate::webservices::{
     TraderGroupModel, TraderModel, UpdateTraderBalanceRequest, UpdateTraderBalanceResponse,
     UpdateTraderRequest,
 };
+use chrono::Utc;
 use error_chain::bail;
 use flurl::{FlUrl, FlUrlMode, FlUrlResponse};
 use http::{Method, StatusCode};
+use rust_extensions::Logger;
+use rustls::ClientConfig;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
 
 #[async_trait::async_trait]
 pub trait WebservicesApiConfig {
@@ -34,8 +51,43 @@ pub struct WebservicesApiClient<C: WebservicesApiConfig> {
     config: C,
     creds: Arc<dyn ManagerCreds + Send + Sync>,
     auth_token: std::sync::RwLock<Option<String>>,
+    cid_token: std::sync::RwLock<Option<String>>,
     use_http2: bool,
     timeout: Duration,
+    metrics: Arc<ConnectorMetrics>,
+    in_flight_calls: std::sync::Mutex<HashMap<u64, InFlightCall>>,
+    next_call_id: AtomicU64,
+    is_shutting_down: AtomicBool,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    retry_policy: Option<RetryPolicy>,
+    balance_change_verifier: Option<Arc<dyn BalanceChangeVerifier + Send + Sync>>,
+    proxy_route: Option<ProxyRoute>,
+}
+
+/// The calls go as plain HTTP to the local relay, which tunnels them through the proxy and
+/// does TLS with the server.
+struct ProxyRoute {
+    proxy: ProxyConfig,
+    logger: Arc<dyn Logger + Send + Sync + 'static>,
+    relay: OnceCell<LocalRelay>,
+    tls_config: OnceCell<Arc<ClientConfig>>,
+}
+
+struct InFlightCall {
+    endpoint: WebservicesApiEndpoint,
+    started: Instant,
+}
+
+/// Removes the call from the in-flight ones when it completes or is cancelled.
+struct InFlightCallGuard<'a> {
+    id: u64,
+    in_flight_calls: &'a std::sync::Mutex<HashMap<u64, InFlightCall>>,
+}
+
+impl Drop for InFlightCallGuard<'_> {
+    fn drop(&mut self) {
+        self.in_flight_calls.lock().unwrap().remove(&self.id);
+    }
 }
 
 impl<C: WebservicesApiConfig> WebservicesApiClient<C> {
@@ -44,15 +96,112 @@ impl<C: WebservicesApiConfig> WebservicesApiClient<C> {
             config,
             creds,
             auth_token: std::sync::RwLock::new(None),
+            cid_token: std::sync::RwLock::new(None),
             use_http2,
             timeout,
+            metrics: Arc::new(ConnectorMetrics::default()),
+            in_flight_calls: Default::default(),
+            next_call_id: AtomicU64::new(0),
+            is_shutting_down: AtomicBool::new(false),
+            rate_limiter: None,
+            retry_policy: None,
+            balance_change_verifier: None,
+            proxy_route: None,
+        }
+    }
+
+    /// Sends the calls through the outbound proxy. Failed tunnels are logged with the logger.
+    pub fn with_proxy(
+        mut self,
+        proxy: ProxyConfig,
+        logger: Arc<dyn Logger + Send + Sync + 'static>,
+    ) -> Self {
+        self.proxy_route = Some(ProxyRoute {
+            proxy,
+            logger,
+            relay: OnceCell::new(),
+            tls_config: OnceCell::new(),
+        });
+        self
+    }
+
+    /// Retries the failed GET calls and the balance changes with `external_id` after transient
+    /// failures: timeouts, connection resets, 5xx and 429 responses.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Required to retry a balance change whose outcome is unknown: before the retry the
+    /// verifier checks whether the change with the same `external_id` was already applied.
+    /// Without it such balance changes are not retried. `ManagerApiClient` implements it with
+    /// the balance history of the Manager API.
+    pub fn with_balance_change_verifier(
+        mut self,
+        verifier: Arc<dyn BalanceChangeVerifier + Send + Sync>,
+    ) -> Self {
+        self.balance_change_verifier = Some(verifier);
+        self
+    }
+
+    /// Limits the calls with the `WEBSERVICES_GROUP` bucket of the limiter. The limiter can be
+    /// shared with other clients.
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Stops accepting new calls and waits until the calls in progress complete or the deadline
+    /// passes. The report contains the calls with unknown outcome.
+    pub async fn shutdown(&self, deadline: Duration) -> WebservicesShutdownReport {
+        self.is_shutting_down.store(true, Ordering::SeqCst);
+        let instant = Instant::now();
+
+        while !self.in_flight_calls.lock().unwrap().is_empty() && instant.elapsed() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
         }
+
+        let unconfirmed_calls = self
+            .in_flight_calls
+            .lock()
+            .unwrap()
+            .values()
+            .map(|call| UnconfirmedCall {
+                endpoint: call.endpoint.get_name(),
+                method: call.endpoint.get_http_method(),
+                login: call.endpoint.get_login(),
+                elapsed: call.started.elapsed(),
+            })
+            .collect();
+
+        WebservicesShutdownReport { unconfirmed_calls }
+    }
+
+    /// Accepts new calls again after `shutdown`.
+    pub fn resume(&self) {
+        self.is_shutting_down.store(false, Ordering::SeqCst);
+    }
+
+    /// Request latency and HTTP status counters per endpoint.
+    pub fn get_metrics(&self) -> Arc<ConnectorMetrics> {
+        Arc::clone(&self.metrics)
     }
 
     pub fn clear_token(&self) {
         let _ = self.auth_token.write().unwrap().take();
     }
 
+    /// Stores the cID token for the next requests to the cID routes (`/cid/ctid/create`,
+    /// `/cid/ctid/link`). The token can be obtained from an authorized Manager API session
+    /// via `ManagerApiClient::get_cid_auth_token`, so no password login is needed for them.
+    pub fn set_cid_token(&self, token: String) {
+        let _ = self.cid_token.write().unwrap().replace(token);
+    }
+
+    pub fn clear_cid_token(&self) {
+        let _ = self.cid_token.write().unwrap().take();
+    }
+
     pub fn is_authorized(&self) -> bool {
         self.auth_token.read().unwrap().is_some()
     }
@@ -120,12 +269,66 @@ impl<C: WebservicesApiConfig> WebservicesApiClient<C> {
     }
 
     /// Changes the balance of a trader entity (including allocating/removing credit).
+    /// With a retry policy the change is retried only if it has `external_id`. If the outcome
+    /// of an attempt is unknown, the change is retried only when the balance change verifier
+    /// does not find it in the balance history.
     pub async fn update_trader_balance(
         &self,
         request: &UpdateTraderBalanceRequest,
     ) -> Result<UpdateTraderBalanceResponse, Error> {
         let endpoint = WebservicesApiEndpoint::UpdateTraderBalance(request.login);
-        self.send_deserialized(endpoint, Some(request)).await
+
+        let (Some(retry_policy), Some(external_id)) = (&self.retry_policy, &request.external_id)
+        else {
+            return self.send_deserialized(endpoint, Some(request)).await;
+        };
+
+        // margin for the difference of the server and host clocks
+        let from_timestamp = Utc::now().timestamp_millis() - 60_000;
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+            let started = Instant::now();
+            let (error, kind) = match self.send_flurl_once(endpoint, Some(request)).await {
+                Ok(response) => return deserialize_response(endpoint, Some(request), &response),
+                Err(err) => err,
+            };
+
+            if !kind.is_transient() || !retry_policy.can_retry(attempt) {
+                return Err(error);
+            }
+
+            if !kind.is_outcome_unknown() {
+                tokio::time::sleep(retry_policy.get_delay(attempt)).await;
+                continue;
+            }
+
+            let Some(verifier) = &self.balance_change_verifier else {
+                return Err(error);
+            };
+
+            // the server may still apply the change after the client stopped waiting
+            let delay = retry_policy.get_verify_delay(attempt, self.timeout, started.elapsed());
+            tokio::time::sleep(delay).await;
+
+            match verifier
+                .find_balance_change(request.login, external_id, from_timestamp)
+                .await
+            {
+                Ok(Some(balance_history_id)) => {
+                    return Ok(UpdateTraderBalanceResponse { balance_history_id })
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    return Err(redact(&format!(
+                        "Balance change outcome is unknown: {}. Failed to verify: {}",
+                        error, err
+                    ))
+                    .into());
+                }
+            }
+        }
     }
 
     /// Updates a trader entity.
@@ -205,68 +408,144 @@ impl<C: WebservicesApiConfig> WebservicesApiClient<C> {
         request: Option<&R>,
     ) -> Result<T, Error> {
         let response = self.send_flurl(endpoint, request).await?;
-        let result: Result<T, _> = serde_json::from_str(&response);
-
-        let Ok(body) = result else {
-            let msg = format!(
-                "Failed to deserialize: {:?}. Url: {:?} {:?}. Request: {:?}. Body: {}",
-                result,
-                endpoint.get_http_method(),
-                String::from(&endpoint),
-                request,
-                response
-            );
-            return Err(msg.into());
-        };
 
-        Ok(body)
+        deserialize_response(endpoint, request, &response)
     }
 
+    /// Sends the call and retries GET requests according to the retry policy.
     async fn send_flurl<R: Serialize + Debug>(
         &self,
         endpoint: WebservicesApiEndpoint,
         request: Option<&R>,
     ) -> Result<String, Error> {
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+            let (error, kind) = match self.send_flurl_once(endpoint, request).await {
+                Ok(response) => return Ok(response),
+                Err(err) => err,
+            };
+
+            let Some(retry_policy) = &self.retry_policy else {
+                return Err(error);
+            };
+
+            if endpoint.get_http_method() != Method::GET
+                || !kind.is_transient()
+                || !retry_policy.can_retry(attempt)
+            {
+                return Err(error);
+            }
+
+            tokio::time::sleep(retry_policy.get_delay(attempt)).await;
+        }
+    }
+
+    /// Makes one attempt of the call. The error is returned with the kind of the failure.
+    async fn send_flurl_once<R: Serialize + Debug>(
+        &self,
+        endpoint: WebservicesApiEndpoint,
+        request: Option<&R>,
+    ) -> Result<String, (Error, FailureKind)> {
+        let get_shutting_down_error = || {
+            let error = format!("Client is shutting down: {}", endpoint.get_name());
+            (error.into(), FailureKind::Permanent)
+        };
+
+        if self.is_shutting_down.load(Ordering::SeqCst) {
+            return Err(get_shutting_down_error());
+        }
+
         let mut request_json = None;
 
         if let Some(request) = request {
-            let body = serde_json::to_string(request)?;
+            let body = serde_json::to_string(request).map_err(get_permanent_failure)?;
             request_json = Some(body.clone());
         }
 
-        let request_bytes: Option<Vec<u8>> = if let Some(request) = request {
-            Some(serde_json::to_string(request)?.into_bytes())
-        } else {
-            None
-        };
-        let (flurl, url) = self.build_flurl(endpoint, request).await?;
+        let request_bytes: Option<Vec<u8>> = request_json.clone().map(String::into_bytes);
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter
+                .acquire(WEBSERVICES_GROUP, endpoint.get_priority())
+                .await;
+        }
+
+        let (flurl, url) = self
+            .build_flurl(endpoint, request)
+            .await
+            .map_err(get_permanent_failure)?;
         let http_method = endpoint.get_http_method();
+        let started = Instant::now();
+        // started before the check, so `shutdown` either waits for the call or the call sees
+        // that the shutdown has started while it was waiting for the token
+        let _in_flight_call = self.start_in_flight_call(endpoint, started);
 
-        let result = if http_method == Method::GET {
-            flurl.get().await
-        } else if http_method == Method::POST {
-            flurl.post(request_bytes).await
-        } else if http_method == Method::PUT {
-            flurl.put(request_bytes).await
-        } else if http_method == Method::PATCH {
-            flurl.patch(request_bytes).await
-        } else if http_method == Method::DELETE {
-            flurl.delete().await
-        } else {
-            panic!("not implemented");
-        };
+        if self.is_shutting_down.load(Ordering::SeqCst) {
+            return Err(get_shutting_down_error());
+        }
+
+        let span = RequestSpan::webservices(&endpoint);
+
+        let result = span
+            .instrument(async {
+                if http_method == Method::GET {
+                    flurl.get().await
+                } else if http_method == Method::POST {
+                    flurl.post(request_bytes).await
+                } else if http_method == Method::PUT {
+                    flurl.put(request_bytes).await
+                } else if http_method == Method::PATCH {
+                    flurl.patch(request_bytes).await
+                } else if http_method == Method::DELETE {
+                    flurl.delete().await
+                } else {
+                    panic!("not implemented");
+                }
+            })
+            .await;
 
         let Ok(resp) = result else {
-            return Err(format!(
+            self.metrics
+                .on_http_response(endpoint.get_name(), None, started.elapsed());
+            span.finish("failed");
+            let error = redact(&format!(
                 "FlUrl failed to receive_body: Url: {}. Request: {:?}. {:?}",
                 url,
                 request_json,
                 result.unwrap_err()
-            )
-            .into());
+            ));
+            return Err((error.into(), FailureKind::from_status_code(None)));
         };
 
-        handle_flurl_text(resp, &request_json, &url, endpoint.get_http_method()).await
+        self.metrics.on_http_response(
+            endpoint.get_name(),
+            Some(resp.get_status_code()),
+            started.elapsed(),
+        );
+        let status_code = resp.get_status_code();
+        span.finish(&status_code.to_string());
+
+        handle_flurl_text(resp, &request_json, &url, endpoint.get_http_method())
+            .await
+            .map_err(|e| (e, FailureKind::from_status_code(Some(status_code))))
+    }
+
+    fn start_in_flight_call(
+        &self,
+        endpoint: WebservicesApiEndpoint,
+        started: Instant,
+    ) -> InFlightCallGuard<'_> {
+        let id = self.next_call_id.fetch_add(1, Ordering::SeqCst);
+        self.in_flight_calls
+            .lock()
+            .unwrap()
+            .insert(id, InFlightCall { endpoint, started });
+
+        InFlightCallGuard {
+            id,
+            in_flight_calls: &self.in_flight_calls,
+        }
     }
 
     pub async fn build_flurl<R: Serialize>(
@@ -274,8 +553,12 @@ impl<C: WebservicesApiConfig> WebservicesApiClient<C> {
         endpoint: WebservicesApiEndpoint,
         request: Option<&R>,
     ) -> Result<(FlUrl, String), Error> {
-        let token = self.get_token_cloned();
-        let base_url = self.config.get_url().await;
+        let token = if endpoint.is_cid() {
+            self.get_cid_token_cloned().or_else(|| self.get_token_cloned())
+        } else {
+            self.get_token_cloned()
+        };
+        let (base_url, host) = self.get_base_url().await?;
         let http_method = endpoint.get_http_method();
 
         let url = if http_method == Method::GET {
@@ -292,10 +575,86 @@ impl<C: WebservicesApiConfig> WebservicesApiClient<C> {
         };
         let flurl = flurl.set_timeout(self.timeout);
         let flurl = self.add_headers(flurl);
+        let flurl = match host {
+            Some(host) => flurl.with_header("Host", host),
+            None => flurl,
+        };
 
         Ok((flurl, url))
     }
 
+    /// Returns the base url for the calls and the `Host` header when it differs from the url.
+    /// With a proxy the url points to the local relay.
+    async fn get_base_url(&self) -> Result<(String, Option<String>), Error> {
+        let url = self.config.get_url().await;
+
+        let Some(proxy_route) = &self.proxy_route else {
+            return Ok((url, None));
+        };
+
+        let (is_https, rest) = if let Some(rest) = url.strip_prefix("https://") {
+            (true, rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            (false, rest)
+        } else {
+            bail!("Invalid url {}: expected http or https scheme", url);
+        };
+
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let host_port = match parse_host_port(authority) {
+            Ok(_) => authority.to_string(),
+            Err(_) => {
+                let host = authority.trim_start_matches('[').trim_end_matches(']');
+                format_host_port(host, if is_https { 443 } else { 80 })
+            }
+        };
+        let (host, _) = parse_host_port(&host_port)?;
+
+        let tls = if is_https {
+            Some(RelayTls {
+                server_name: parse_server_name(&host)?,
+                config: self.get_tls_config(proxy_route).await?,
+            })
+        } else {
+            None
+        };
+        let relay = proxy_route
+            .relay
+            .get_or_try_init(|| LocalRelay::start(Arc::clone(&proxy_route.logger)))
+            .await?;
+        relay
+            .set_target(RelayTarget {
+                host_port,
+                proxy: Some(proxy_route.proxy.clone()),
+                tls,
+            })
+            .await;
+
+        let base_url = format!("http://{}{}", relay.get_local_addr(), path);
+
+        Ok((base_url, Some(authority.to_string())))
+    }
+
+    /// TLS with the default roots. The protocol is negotiated with ALPN because the relay
+    /// does TLS instead of FlUrl.
+    async fn get_tls_config(&self, proxy_route: &ProxyRoute) -> Result<Arc<ClientConfig>, Error> {
+        let config = proxy_route
+            .tls_config
+            .get_or_try_init(|| async {
+                let mut config = (*ManagerApiTlsConfig::default().build_client_config()?).clone();
+                config.alpn_protocols = if self.use_http2 {
+                    vec![b"h2".to_vec()]
+                } else {
+                    vec![b"http/1.1".to_vec()]
+                };
+
+                Ok::<_, String>(Arc::new(config))
+            })
+            .await?;
+
+        Ok(Arc::clone(config))
+    }
+
     fn add_headers(&self, flurl: FlUrl) -> FlUrl {
         let json_content_str = "application/json";
 
@@ -342,6 +701,10 @@ impl<C: WebservicesApiConfig> WebservicesApiClient<C> {
     fn get_token_cloned(&self) -> Option<String> {
         (*self.auth_token.read().unwrap()).clone()
     }
+
+    fn get_cid_token_cloned(&self) -> Option<String> {
+        (*self.cid_token.read().unwrap()).clone()
+    }
 }
 
 async fn handle_flurl_text(
@@ -354,7 +717,11 @@ async fn handle_flurl_text(
     let result = response.receive_body().await;
 
     let Ok(body_bytes) = result else {
-        return Err(format!("FlUrl failed to receive_body: {:?}", result.unwrap_err()).into());
+        return Err(redact(&format!(
+            "FlUrl failed to receive_body: {:?}",
+            result.unwrap_err()
+        ))
+        .into());
     };
 
     let body_str = String::from_utf8(body_bytes).unwrap();
@@ -362,33 +729,59 @@ async fn handle_flurl_text(
     match status_code {
         StatusCode::OK | StatusCode::CREATED | StatusCode::NO_CONTENT => Ok(body_str),
         StatusCode::INTERNAL_SERVER_ERROR => {
-            bail!(format!(
+            bail!(redact(&format!(
                 "Internal Server Error. Url: {request_method:?} {request_url}"
-            ));
+            )));
         }
         StatusCode::SERVICE_UNAVAILABLE => {
-            bail!(format!(
+            bail!(redact(&format!(
                 "Service Unavailable. Url: {request_method:?} {request_url}"
-            ));
+            )));
         }
         StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
-            bail!(format!(
+            bail!(redact(&format!(
                 "Unauthorized or forbidden. Url: {request_method:?} {request_url}"
-            ));
+            )));
         }
         StatusCode::BAD_REQUEST => {
             let error = body_str;
-            bail!(format!(
+            bail!(redact(&format!(
                 "Received bad request status. Url: {request_method:?} {request_url}. Request: {request_json:?}. Response: {error:?}"
-            ));
+            )));
         }
         code => {
             let error = body_str;
-            bail!(format!("Received response code: {code:?}. Url: {request_method:?} {request_url}. Request: {request_json:?} Response: {error:?}"));
+            bail!(redact(&format!("Received response code: {code:?}. Url: {request_method:?} {request_url}. Request: {request_json:?} Response: {error:?}")));
         }
     }
 }
 
+fn deserialize_response<R: Debug, T: DeserializeOwned>(
+    endpoint: WebservicesApiEndpoint,
+    request: Option<&R>,
+    response: &str,
+) -> Result<T, Error> {
+    let result: Result<T, _> = serde_json::from_str(response);
+
+    let Ok(body) = result else {
+        let msg = format!(
+            "Failed to deserialize: {:?}. Url: {:?} {:?}. Request: {:?}. Body: {}",
+            result.err(),
+            endpoint.get_http_method(),
+            String::from(&endpoint),
+            request,
+            response
+        );
+        return Err(redact(&msg).into());
+    };
+
+    Ok(body)
+}
+
+fn get_permanent_failure(error: impl Into<Error>) -> (Error, FailureKind) {
+    (error.into(), FailureKind::Permanent)
+}
+
 pub fn parse_positions<T: DeserializeOwned + Debug>(data: &str) -> Result<Vec<T>, Error> {
     let mut reader = csv::ReaderBuilder::new()
         .has_headers(true)
@@ -400,7 +793,7 @@ pub fn parse_positions<T: DeserializeOwned + Debug>(data: &str) -> Result<Vec<T>
 
         let Ok(position) = result else {
             let msg = format!("Failed to parse: {:?}. Resp: {data}", result.unwrap_err());
-            return Err(msg.into());
+            return Err(redact(&msg).into());
         };
 
         positions.push(position)
@@ -411,8 +804,121 @@ pub fn parse_positions<T: DeserializeOwned + Debug>(data: &str) -> Result<Vec<T>
 
 #[cfg(test)]
 mod tests {
-    use crate::webservices::api_client::parse_positions;
+    use crate::models::ManagerCreds;
+    use crate::proxy::ProxyConfig;
+    use crate::rate_limiter::{
+        RateLimiter, RequestPriority, TokenBucketSettings, WEBSERVICES_GROUP,
+    };
+    use crate::webservices::api_client::{
+        parse_positions, WebservicesApiClient, WebservicesApiConfig,
+    };
+    use crate::webservices::endpoints::WebservicesApiEndpoint;
     use crate::webservices::{ClosedPositionModel, OpenedPositionModel};
+    use rust_extensions::Logger;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::oneshot;
+
+    struct TestConfig(String);
+
+    #[async_trait::async_trait]
+    impl WebservicesApiConfig for TestConfig {
+        async fn get_url(&self) -> String {
+            self.0.clone()
+        }
+    }
+
+    struct TestCreds;
+
+    #[async_trait::async_trait]
+    impl ManagerCreds for TestCreds {
+        async fn get_password(&self) -> String {
+            "password".to_string()
+        }
+
+        async fn get_login(&self) -> i64 {
+            1
+        }
+    }
+
+    struct EmptyLogger;
+
+    impl Logger for EmptyLogger {
+        fn write_info(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+        fn write_warning(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+        fn write_error(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+        fn write_fatal_error(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+        fn write_debug_info(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+    }
+
+    async fn read_head(socket: &mut TcpStream) -> String {
+        let mut head = Vec::new();
+
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(socket.read_u8().await.unwrap());
+        }
+
+        String::from_utf8(head).unwrap()
+    }
+
+    #[tokio::test]
+    async fn sends_calls_through_proxy() {
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_address = server.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut socket, _) = server.accept().await.unwrap();
+            let request = read_head(&mut socket).await;
+            assert!(request.starts_with("GET /api/"));
+            assert!(request.contains("/webserv/symbols HTTP/1.1"));
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                .await
+                .unwrap();
+        });
+
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_address = proxy.local_addr().unwrap().to_string();
+        let (connect_sender, connect_receiver) = oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = proxy.accept().await.unwrap();
+            let request = read_head(&mut socket).await;
+            let target = request.split_whitespace().nth(1).unwrap().to_string();
+            connect_sender.send(request).unwrap();
+
+            let mut upstream = TcpStream::connect(target).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                .await
+                .unwrap();
+            let _ = tokio::io::copy_bidirectional(&mut socket, &mut upstream).await;
+        });
+
+        let config = TestConfig(format!("http://{}/api", server_address));
+        let client =
+            WebservicesApiClient::new(config, Arc::new(TestCreds), false, Duration::from_secs(5))
+                .with_proxy(ProxyConfig::http(proxy_address), Arc::new(EmptyLogger));
+        let request: Option<&String> = None;
+        let (_, url) = client
+            .build_flurl(WebservicesApiEndpoint::GetSymbols, request)
+            .await
+            .unwrap();
+
+        // FlUrl would send the same request to the relay url
+        let relay_url = url.strip_prefix("http://").unwrap();
+        let (relay_address, path) = relay_url.split_at(relay_url.find('/').unwrap());
+        let mut stream = TcpStream::connect(relay_address).await.unwrap();
+        let request = format!("GET {path} HTTP/1.1\r\nHost: {server_address}\r\n\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        let connect_request = connect_receiver.await.unwrap();
+        assert!(connect_request.starts_with(&format!("CONNECT {} HTTP/1.1", server_address)));
+        assert!(response.ends_with("ok"));
+    }
 
     #[test]
     fn parses_closed_positions() {
@@ -441,4 +947,32 @@ mod tests {
         let positions = result.unwrap();
         assert_eq!(positions.len(), 2);
     }
+
+    #[tokio::test]
+    async fn rejects_call_waiting_for_token_on_shutdown() {
+        let settings = TokenBucketSettings {
+            capacity: 1,
+            refill_per_second: 1.0,
+        };
+        let limiter = Arc::new(
+            RateLimiter::default()
+                .with_group(WEBSERVICES_GROUP, settings)
+                .unwrap(),
+        );
+        limiter
+            .acquire(WEBSERVICES_GROUP, RequestPriority::Normal)
+            .await;
+        let config = TestConfig("http://127.0.0.1:1".to_string());
+        let client =
+            WebservicesApiClient::new(config, Arc::new(TestCreds), false, Duration::from_secs(5))
+                .with_rate_limiter(limiter);
+
+        let (result, report) = tokio::join!(client.get_symbols(), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            client.shutdown(Duration::from_secs(5)).await
+        });
+
+        assert!(result.unwrap_err().to_string().contains("shutting down"));
+        assert!(report.is_clean());
+    }
 }
@@ -1,6 +1,7 @@
+use crate::rate_limiter::RequestPriority;
 use http::Method;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, strum::IntoStaticStr)]
 pub enum WebservicesApiEndpoint {
     CreateManagerToken,
     CreateCtid,
@@ -62,6 +63,40 @@ impl From<&WebservicesApiEndpoint> for String {
 }
 
 impl WebservicesApiEndpoint {
+    /// Returns the endpoint name without parameters, e.g. "GetTrader".
+    pub fn get_name(&self) -> &'static str {
+        self.into()
+    }
+
+    /// Returns TRUE for the cID routes which accept a manager token issued by the Manager API.
+    pub fn is_cid(&self) -> bool {
+        matches!(
+            self,
+            WebservicesApiEndpoint::CreateCtid | WebservicesApiEndpoint::LinkCtid
+        )
+    }
+
+    /// Returns the trader login for the endpoints that require it.
+    pub fn get_login(&self) -> Option<i64> {
+        match self {
+            WebservicesApiEndpoint::UpdateTrader(login)
+            | WebservicesApiEndpoint::UpdateTraderBalance(login)
+            | WebservicesApiEndpoint::GetTrader(login) => Some(*login),
+            _ => None,
+        }
+    }
+
+    /// Trader updates go first as they change access rights, bulk reads go last.
+    pub fn get_priority(&self) -> RequestPriority {
+        match self {
+            WebservicesApiEndpoint::UpdateTrader(_) => RequestPriority::High,
+            WebservicesApiEndpoint::GetTraders | WebservicesApiEndpoint::GetClosedPositions => {
+                RequestPriority::Low
+            }
+            _ => RequestPriority::Normal,
+        }
+    }
+
     pub fn get_http_method(&self) -> Method {
         match &self {
             WebservicesApiEndpoint::CreateManagerToken => Method::POST,
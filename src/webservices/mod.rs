@@ -3,5 +3,7 @@ pub mod endpoints;
 pub mod errors;
 pub mod models;
 pub mod register_user_flow;
+pub mod retry;
+pub mod shutdown;
 
 pub use models::*;
@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+/// Retries of the calls that are safe to repeat: GET requests and balance changes with
+/// `external_id`. Other calls are sent once.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Max count of attempts including the first one.
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    /// Extra wait before a balance change with unknown outcome is verified. The verification
+    /// starts no earlier than the HTTP timeout plus this delay after the attempt started, so
+    /// a call still processed by the server is not missed.
+    pub settle_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            multiplier: 2.0,
+            settle_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay after the failed attempt with the specified one-based number.
+    pub fn get_delay(&self, attempt: u32) -> Duration {
+        let delay = self.initial_delay.as_secs_f64()
+            * self
+                .multiplier
+                .max(1.0)
+                .powi(attempt.saturating_sub(1) as i32);
+
+        Duration::from_secs_f64(delay.min(self.max_delay.as_secs_f64()))
+    }
+
+    pub fn can_retry(&self, attempt: u32) -> bool {
+        attempt < self.max_attempts
+    }
+
+    /// Returns the delay before verifying the attempt with unknown outcome that started
+    /// `elapsed` ago.
+    pub fn get_verify_delay(&self, attempt: u32, timeout: Duration, elapsed: Duration) -> Duration {
+        let settled = (timeout + self.settle_delay).saturating_sub(elapsed);
+
+        self.get_delay(attempt).max(settled)
+    }
+}
+
+/// Checks whether a balance change was applied when its response was lost.
+#[async_trait::async_trait]
+pub trait BalanceChangeVerifier {
+    /// Returns the balance history id of the change of the trader with the `external_id`
+    /// made after `from_timestamp` (UNIX timestamp in milliseconds), or `None` if there is
+    /// no such change.
+    async fn find_balance_change(
+        &self,
+        login: i64,
+        external_id: &str,
+        from_timestamp: i64,
+    ) -> Result<Option<i64>, String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FailureKind {
+    /// The call was processed and failed, repeating it gives the same result.
+    Permanent,
+    /// The call may succeed when repeated. If the outcome is unknown, the call may have
+    /// been already processed by the server.
+    Transient { is_outcome_unknown: bool },
+}
+
+impl FailureKind {
+    /// Classifies the failure by the status code of the response. `None` means that no
+    /// response was received (e.g. timeout or connection reset).
+    pub fn from_status_code(status_code: Option<u16>) -> Self {
+        match status_code {
+            None => Self::Transient {
+                is_outcome_unknown: true,
+            },
+            Some(429) => Self::Transient {
+                is_outcome_unknown: false,
+            },
+            // the body of a successful response was not received
+            Some(200..=299) | Some(500) | Some(502..=504) => Self::Transient {
+                is_outcome_unknown: true,
+            },
+            Some(_) => Self::Permanent,
+        }
+    }
+
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Transient { .. })
+    }
+
+    pub fn is_outcome_unknown(&self) -> bool {
+        matches!(
+            self,
+            Self::Transient {
+                is_outcome_unknown: true
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::webservices::retry::{FailureKind, RetryPolicy};
+    use std::time::Duration;
+
+    #[test]
+    fn calculates_retry_delay() {
+        let policy = RetryPolicy {
+            max_attempts: 4,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+            multiplier: 2.0,
+            settle_delay: Duration::from_secs(1),
+        };
+
+        assert_eq!(policy.get_delay(1), Duration::from_millis(100));
+        assert_eq!(policy.get_delay(2), Duration::from_millis(200));
+        assert_eq!(policy.get_delay(3), Duration::from_millis(300));
+        assert!(policy.can_retry(3));
+        assert!(!policy.can_retry(4));
+
+        let timeout = Duration::from_secs(10);
+        assert_eq!(
+            policy.get_verify_delay(1, timeout, Duration::from_secs(10)),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            policy.get_verify_delay(1, timeout, Duration::from_secs(20)),
+            Duration::from_millis(100)
+        );
+    }
+
+    #[test]
+    fn classifies_failures() {
+        assert!(FailureKind::from_status_code(None).is_outcome_unknown());
+        assert!(FailureKind::from_status_code(Some(503)).is_outcome_unknown());
+
+        let throttled = FailureKind::from_status_code(Some(429));
+        assert!(throttled.is_transient() && !throttled.is_outcome_unknown());
+
+        assert!(!FailureKind::from_status_code(Some(400)).is_transient());
+        assert!(!FailureKind::from_status_code(Some(401)).is_transient());
+    }
+}
@@ -0,0 +1,25 @@
+use http::Method;
+use std::time::Duration;
+
+/// HTTP call that did not complete before the shutdown deadline.
+#[derive(Debug, Clone)]
+pub struct UnconfirmedCall {
+    pub endpoint: &'static str,
+    pub method: Method,
+    pub login: Option<i64>,
+    /// Time since the call was started.
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WebservicesShutdownReport {
+    /// Calls in progress at the deadline. Their outcome is unknown: the server may have
+    /// processed them.
+    pub unconfirmed_calls: Vec<UnconfirmedCall>,
+}
+
+impl WebservicesShutdownReport {
+    pub fn is_clean(&self) -> bool {
+        self.unconfirmed_calls.is_empty()
+    }
+}